@@ -1,7 +1,8 @@
 use criterion::{criterion_group, criterion_main};
 use criterion::Criterion;
 
-use burstsort::benching::{bench_english, bench_random_count, bench_random_length};
+use burstsort::benching::{bench_batches, bench_english, bench_map, bench_random_count,
+                          bench_random_length, bench_word_count};
 
 #[global_allocator]
 static GLOBAL: jemallocator::Jemalloc = jemallocator::Jemalloc;
@@ -10,6 +11,18 @@ fn english(c: &mut Criterion) {
     bench_english(c, "jemalloc")
 }
 
+fn map(c: &mut Criterion) {
+    bench_map(c, "jemalloc")
+}
+
+fn batches(c: &mut Criterion) {
+    bench_batches(c, "jemalloc")
+}
+
+fn word_count(c: &mut Criterion) {
+    bench_word_count(c, "jemalloc")
+}
+
 fn random_count(c: &mut Criterion) {
     bench_random_count(c, "jemalloc")
 }
@@ -23,5 +36,8 @@ criterion_group!(
     random_length,
     random_count,
     english,
+    map,
+    word_count,
+    batches,
 );
 criterion_main!(benches);
@@ -1,12 +1,25 @@
 use criterion::{criterion_group, criterion_main};
 use criterion::Criterion;
 
-use burstsort::benching::{bench_english, bench_random_count, bench_random_length};
+use burstsort::benching::{bench_batches, bench_english, bench_map, bench_random_count,
+                          bench_random_length, bench_word_count};
 
 fn english(c: &mut Criterion) {
     bench_english(c, "system")
 }
 
+fn map(c: &mut Criterion) {
+    bench_map(c, "system")
+}
+
+fn batches(c: &mut Criterion) {
+    bench_batches(c, "system")
+}
+
+fn word_count(c: &mut Criterion) {
+    bench_word_count(c, "system")
+}
+
 fn random_count(c: &mut Criterion) {
     bench_random_count(c, "system")
 }
@@ -20,5 +33,8 @@ criterion_group!(
     random_length,
     random_count,
     english,
+    map,
+    word_count,
+    batches,
 );
 criterion_main!(benches);
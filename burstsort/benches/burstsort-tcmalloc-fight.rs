@@ -2,7 +2,8 @@ use criterion::{criterion_group, criterion_main};
 use criterion::Criterion;
 use tcmalloc::TCMalloc;
 
-use burstsort::benching::{bench_english, bench_random_count, bench_random_length};
+use burstsort::benching::{bench_batches, bench_english, bench_map, bench_random_count,
+                          bench_random_length, bench_word_count};
 
 #[global_allocator]
 static GLOBAL: TCMalloc = TCMalloc;
@@ -11,6 +12,18 @@ fn english(c: &mut Criterion) {
     bench_english(c, "tcmalloc")
 }
 
+fn map(c: &mut Criterion) {
+    bench_map(c, "tcmalloc")
+}
+
+fn batches(c: &mut Criterion) {
+    bench_batches(c, "tcmalloc")
+}
+
+fn word_count(c: &mut Criterion) {
+    bench_word_count(c, "tcmalloc")
+}
+
 fn random_count(c: &mut Criterion) {
     bench_random_count(c, "tcmalloc")
 }
@@ -24,5 +37,8 @@ criterion_group!(
     random_length,
     random_count,
     english,
+    map,
+    word_count,
+    batches,
 );
 criterion_main!(benches);
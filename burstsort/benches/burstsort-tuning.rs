@@ -1,13 +1,29 @@
 use criterion::{criterion_group, criterion_main, Throughput, BenchmarkId};
 use criterion::Criterion;
 
-use burstsort::{ASCII_CONFIG, BurstConfig};
+use burstsort::ASCII_CONFIG;
 use burstsort::benching::read_file_alpha;
 
 #[global_allocator]
 static GLOBAL: jemallocator::Jemalloc = jemallocator::Jemalloc;
 
 const BURST_STR: &str = "par-burstsort";
+const BLOCKS_STR: &str = "par-burstsort-blocks";
+const SEQ_BURST_STR: &str = "burstsort";
+const SEQ_BLOCKS_STR: &str = "burstsort-blocks";
+
+/// Burst limit for a level of the trie.
+type Schedule = fn(usize) -> usize;
+
+/// Burst limits by level, from bursting early near the root to bursting late deep down.
+const SCHEDULES: [(&str, Schedule); 6] = [
+    ("flat", |_| 16384),
+    ("shallow-1024", |level| if level == 0 { 1024 } else { 16384 }),
+    ("shallow-4096", |level| if level < 2 { 4096 } else { 16384 }),
+    ("ramp", |level| 1024 << level.min(5)),
+    ("deep-65536", |level| if level < 2 { 16384 } else { 65536 }),
+    ("ramp-steep", |level| 256 << (2 * level.min(4))),
+];
 
 fn burst_limit(c: &mut Criterion) {
     let mut group = c.benchmark_group("tune-burst-limit");
@@ -21,10 +37,7 @@ fn burst_limit(c: &mut Criterion) {
         group.bench_function(
             BenchmarkId::new(BURST_STR, burst_limit),
             |b| {
-                let config = BurstConfig {
-                    burst_limit,
-                    ..ASCII_CONFIG
-                };
+                let config = ASCII_CONFIG.with_burst_limit(burst_limit);
                 b.iter(|| burstsort::par_burstsort(&mut text.clone(), &config));
             },
         );
@@ -43,19 +56,75 @@ fn initial_capacity(c: &mut Criterion) {
         group.bench_function(
             BenchmarkId::new(BURST_STR, initial_capacity),
             |b| {
-                let config = BurstConfig {
-                    initial_capacity,
-                    ..ASCII_CONFIG
-                };
+                let config = ASCII_CONFIG.with_initial_capacity(initial_capacity);
+                b.iter(|| burstsort::par_burstsort(&mut text.clone(), &config));
+            },
+        );
+
+        // for block buckets, this is the size of the first block
+        group.bench_function(
+            BenchmarkId::new(BLOCKS_STR, initial_capacity),
+            |b| {
+                let config = ASCII_CONFIG
+                    .with_initial_capacity(initial_capacity)
+                    .with_block_buckets(true);
+                b.iter(|| burstsort::par_burstsort(&mut text.clone(), &config));
+            },
+        );
+    }
+}
+
+fn burst_schedule(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tune-burst-schedule");
+
+    let text = read_file_alpha("data/eng_news_2020_1M/eng_news_2020_1M-sentences.txt", false);
+
+    for (name, schedule) in SCHEDULES {
+        group.bench_function(
+            BenchmarkId::new(BURST_STR, name),
+            |b| {
+                let config = ASCII_CONFIG.with_burst_schedule(schedule);
                 b.iter(|| burstsort::par_burstsort(&mut text.clone(), &config));
             },
         );
     }
 }
 
+/// Short, often repeated words make bursts a large share of the sort, and low limits burst
+/// more often. Runs on one thread, so that bursting is not hidden behind the parallel sorts.
+fn burst_distribution(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tune-burst-distribution");
+
+    let text = read_file_alpha("data/eng_news_2020_1M/eng_news_2020_1M-sentences.txt", false);
+
+    let limits = [64, 256, 1024, 4096, 16384];
+
+    for burst_limit in limits {
+        group.bench_function(
+            BenchmarkId::new(SEQ_BURST_STR, burst_limit),
+            |b| {
+                let config = ASCII_CONFIG.with_burst_limit(burst_limit);
+                b.iter(|| burstsort::burstsort(&mut text.clone(), &config));
+            },
+        );
+
+        group.bench_function(
+            BenchmarkId::new(SEQ_BLOCKS_STR, burst_limit),
+            |b| {
+                let config = ASCII_CONFIG
+                    .with_burst_limit(burst_limit)
+                    .with_block_buckets(true);
+                b.iter(|| burstsort::burstsort(&mut text.clone(), &config));
+            },
+        );
+    }
+}
+
 criterion_group!(
     benches,
     burst_limit,
-    initial_capacity
+    initial_capacity,
+    burst_schedule,
+    burst_distribution
 );
 criterion_main!(benches);
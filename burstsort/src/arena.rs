@@ -0,0 +1,266 @@
+//! Burst trie which allocates from its own arenas, rather than holding vectors in every node.
+//!
+//! Nodes live in a single arena and refer to their children by index, and buckets are chains of
+//! fixed-size blocks carved out of a shared slab, as in the original burstsort papers. Only a
+//! handful of large allocations are made, and the whole trie is freed in one go.
+
+use std::borrow::Borrow;
+use std::iter;
+use std::marker::PhantomData;
+use std::mem;
+
+use crate::trie::{BurstConfig, ItemKeys, sort_bucket};
+
+/// Number of items held by each block of a bucket.
+const BLOCK_SIZE: usize = 32;
+
+/// Marks the end of a chain of blocks.
+const NIL: usize = usize::MAX;
+
+/// Chain of blocks holding the items of a bucket.
+#[derive(Copy, Clone)]
+struct Chain {
+    head: usize,
+    tail: usize,
+    len: usize,
+    /// Total key length of the items, for [BurstConfig::burst_bytes].
+    key_len: usize,
+}
+
+impl Chain {
+    const EMPTY: Chain = Chain {
+        head: NIL,
+        tail: NIL,
+        len: 0,
+        key_len: 0,
+    };
+}
+
+#[derive(Copy, Clone)]
+enum NodeKind {
+    Bucket(Chain),
+    /// Index of the first of the `classes` children of the node, which are contiguous.
+    Burst(usize),
+}
+
+#[derive(Copy, Clone)]
+struct Node {
+    matches: Chain,
+    kind: NodeKind,
+}
+
+impl Node {
+    const EMPTY: Node = Node {
+        matches: Chain::EMPTY,
+        kind: NodeKind::Bucket(Chain::EMPTY),
+    };
+}
+
+pub struct ArenaTrie<C, T, I> {
+    config: C,
+    /// Every node of the trie, with the root first.
+    nodes: Vec<Node>,
+    /// Item storage, `BLOCK_SIZE` slots per block.
+    slots: Vec<Option<T>>,
+    /// Next block in the chain of each block.
+    next: Vec<usize>,
+    /// Blocks released by bursts, reused before the slab grows.
+    free: Vec<usize>,
+    _phantom: PhantomData<I>,
+}
+
+impl<C, T, I> ArenaTrie<C, T, I>
+    where T: AsRef<[I]>,
+          C: Borrow<BurstConfig>,
+          I: Into<usize> + Clone + Ord
+{
+    pub fn new(config: C) -> Self {
+        Self {
+            config,
+            nodes: vec![Node::EMPTY],
+            slots: Vec::new(),
+            next: Vec::new(),
+            free: Vec::new(),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn insert(&mut self, item: T) {
+        self.insert_at(0, 0, item);
+    }
+
+    /// Inserts the item below the given node, which is at `level`.
+    fn insert_at(&mut self, mut node: usize, mut level: usize, item: T) {
+        loop {
+            let radix = match item.as_ref().get(level) {
+                Some(radix) => radix.clone().into(),
+                None => {
+                    let mut matches = self.nodes[node].matches;
+                    self.push(&mut matches, item);
+                    self.nodes[node].matches = matches;
+                    return;
+                }
+            };
+
+            match self.nodes[node].kind {
+                NodeKind::Burst(children) => {
+                    node = children + radix;
+                    level += 1;
+                }
+                NodeKind::Bucket(mut chain) => {
+                    self.push(&mut chain, item);
+                    self.nodes[node].kind = NodeKind::Bucket(chain);
+
+                    if self.over_limit(&chain, level) {
+                        self.burst(node, level);
+                    }
+
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Replaces the bucket of a node with a table of child nodes, keyed by the next radix.
+    ///
+    /// Children left over the limits are burst in turn from a worklist rather than by recursing,
+    /// as long shared prefixes can make the trie very deep.
+    fn burst(&mut self, node: usize, level: usize) {
+        let classes = self.config.borrow().classes;
+        let mut pending = vec![(node, level)];
+
+        while let Some((node, level)) = pending.pop() {
+            let chain = match self.nodes[node].kind {
+                NodeKind::Bucket(chain) => chain,
+                NodeKind::Burst(_) => continue,
+            };
+
+            let children = self.nodes.len();
+
+            self.nodes.extend(iter::repeat_n(Node::EMPTY, classes));
+            self.nodes[node].kind = NodeKind::Burst(children);
+
+            let mut items = Vec::with_capacity(chain.len);
+            self.drain_chain(chain, &mut items);
+
+            for x in items {
+                let child = children + x.as_ref()[level].clone().into();
+
+                if x.as_ref().len() > level + 1 {
+                    let mut chain = match self.nodes[child].kind {
+                        NodeKind::Bucket(chain) => chain,
+                        NodeKind::Burst(_) => unreachable!(),
+                    };
+
+                    self.push(&mut chain, x);
+                    self.nodes[child].kind = NodeKind::Bucket(chain);
+                } else {
+                    let mut matches = self.nodes[child].matches;
+                    self.push(&mut matches, x);
+                    self.nodes[child].matches = matches;
+                }
+            }
+
+            for (index, child) in self.nodes[children..].iter().enumerate() {
+                if let NodeKind::Bucket(chain) = &child.kind {
+                    if self.over_limit(chain, level + 1) {
+                        pending.push((children + index, level + 1));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether a bucket at `level` is over the burst limits, by the same rule as the nodes of
+    /// [crate::trie::TrieNode].
+    fn over_limit(&self, chain: &Chain, level: usize) -> bool {
+        let config = self.config.borrow();
+        let bytes = (chain.key_len - level * chain.len) * mem::size_of::<I>();
+
+        chain.len > config.burst_limit_at(level)
+            || (chain.len > 1 && config.burst_bytes.is_some_and(|max| bytes > max))
+    }
+
+    /// Appends an item to the end of a chain, adding a block if the last one is full.
+    fn push(&mut self, chain: &mut Chain, item: T) {
+        if chain.len.is_multiple_of(BLOCK_SIZE) {
+            let block = self.alloc_block();
+
+            if chain.head == NIL {
+                chain.head = block;
+            } else {
+                self.next[chain.tail] = block;
+            }
+
+            chain.tail = block;
+        }
+
+        chain.key_len += item.as_ref().len();
+        self.slots[chain.tail * BLOCK_SIZE + chain.len % BLOCK_SIZE] = Some(item);
+        chain.len += 1;
+    }
+
+    fn alloc_block(&mut self) -> usize {
+        if let Some(block) = self.free.pop() {
+            self.next[block] = NIL;
+            return block;
+        }
+
+        self.next.push(NIL);
+        self.slots.extend(iter::repeat_with(|| None).take(BLOCK_SIZE));
+
+        self.next.len() - 1
+    }
+
+    /// Moves the items of a chain into the target in order, releasing its blocks.
+    fn drain_chain(&mut self, chain: Chain, target: &mut Vec<T>) {
+        let mut block = chain.head;
+        let mut remaining = chain.len;
+
+        while remaining > 0 {
+            let len = remaining.min(BLOCK_SIZE);
+            let start = block * BLOCK_SIZE;
+
+            target.extend(self.slots[start..start + len].iter_mut().map(|x| x.take().unwrap()));
+            self.free.push(block);
+
+            remaining -= len;
+            block = self.next[block];
+        }
+    }
+
+    /// Sorts the contents of the trie and drains them into the target, in order.
+    pub fn merge(&mut self, target: &mut Vec<T>) {
+        self.drain_node(0, 0, target);
+
+        self.nodes.truncate(1);
+        self.nodes[0] = Node::EMPTY;
+    }
+
+    fn drain_node(&mut self, node: usize, level: usize, target: &mut Vec<T>) {
+        let classes = self.config.borrow().classes;
+
+        // nodes still to drain, walked without recursing as the trie may be very deep
+        let mut pending = vec![(node, level)];
+
+        while let Some((node, level)) = pending.pop() {
+            let Node { matches, kind } = mem::replace(&mut self.nodes[node], Node::EMPTY);
+
+            // exact matches come first
+            self.drain_chain(matches, target);
+
+            match kind {
+                NodeKind::Bucket(chain) => {
+                    let start = target.len();
+                    self.drain_chain(chain, target);
+
+                    let long = self.config.borrow().hint_long;
+                    sort_bucket(&mut target[start..], &ItemKeys, level, long);
+                }
+                NodeKind::Burst(children) => {
+                    pending.extend((children..children + classes).rev().map(|x| (x, level + 1)));
+                }
+            }
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::{BTreeMap, HashMap};
 use std::time::{Duration, Instant};
 
 use criterion::{BenchmarkId, black_box, Criterion, Throughput};
@@ -5,7 +6,8 @@ use rand::distributions::{Alphanumeric, Distribution, Uniform};
 use rand::distributions::uniform::SampleUniform;
 use unicode_segmentation::UnicodeSegmentation;
 
-use crate::{ASCII_CONFIG, burstsort, LONG_ASCII_CONFIG, par_burstsort};
+use crate::{arena_burstsort, ASCII_CONFIG, BurstCounter, BurstMap, BurstSorter, COLLECTION_CONFIG,
+            burstsort, burstsort_refs, LONG_ASCII_CONFIG, par_burstsort, par_burstsort_refs};
 use rayon::prelude::ParallelSliceMut;
 
 const LENGTH: usize = 2_000_000;
@@ -14,10 +16,19 @@ const PAR_BURST_STR: &str = "par-burstsort";
 const PAR_LONG_BURST_STR: &str = "par-burstsort-long";
 const BURST_STR: &str = "burstsort";
 const LONG_BURST_STR: &str = "burstsort-long";
+const ARENA_BURST_STR: &str = "arena-burstsort";
+const PAR_BURST_REFS_STR: &str = "par-burstsort-refs";
+const BURST_REFS_STR: &str = "burstsort-refs";
 const STD_STABLE_STR: &str = "std-stable";
 const STD_UNSTABLE_STR: &str = "std-unstable";
 const RAYON_STABLE_STR: &str = "rayon-par-stable";
 const RAYON_UNSTABLE_STR: &str = "rayon-par-unstable";
+const BURST_MAP_STR: &str = "burstmap";
+const BTREE_MAP_STR: &str = "btreemap";
+const HASH_MAP_STR: &str = "hashmap";
+const BURST_COUNTER_STR: &str = "burstcounter";
+const PAR_BURST_COUNTER_STR: &str = "par-burstcounter";
+const BURST_SORTER_STR: &str = "burstsorter-reused";
 
 
 pub fn bench_english(c: &mut Criterion, allocator: &str) {
@@ -25,6 +36,226 @@ pub fn bench_english(c: &mut Criterion, allocator: &str) {
     bench_with_text(c, "compare-encoding-ascii-alpha", allocator, text);
 }
 
+pub fn bench_map(c: &mut Criterion, allocator: &str) {
+    let text = read_file_alpha("data/eng_news_2020_1M/eng_news_2020_1M-sentences.txt", false);
+
+    let name = format!("{}-compare-map-english", allocator);
+
+    let mut group = c.benchmark_group(name);
+
+    group.throughput(Throughput::Elements(text.len() as u64));
+
+    group.bench_function(
+        BenchmarkId::new("insert", BURST_MAP_STR),
+        |b| {
+            b.iter(|| {
+                let mut map = BurstMap::new();
+                for x in &text {
+                    *map.entry(x.as_str()).or_insert(0usize) += 1;
+                }
+                map
+            });
+        },
+    );
+
+    group.bench_function(
+        BenchmarkId::new("insert", BTREE_MAP_STR),
+        |b| {
+            b.iter(|| {
+                let mut map = BTreeMap::new();
+                for x in &text {
+                    *map.entry(x.as_str()).or_insert(0usize) += 1;
+                }
+                map
+            });
+        },
+    );
+
+    group.bench_function(
+        BenchmarkId::new("insert", HASH_MAP_STR),
+        |b| {
+            b.iter(|| {
+                let mut map = HashMap::new();
+                for x in &text {
+                    *map.entry(x.as_str()).or_insert(0usize) += 1;
+                }
+                map
+            });
+        },
+    );
+
+    let burst_map: BurstMap<String, usize> = text.iter().map(|x| (x.clone(), x.len())).collect();
+    let btree_map: BTreeMap<String, usize> = text.iter().map(|x| (x.clone(), x.len())).collect();
+    let hash_map: HashMap<String, usize> = text.iter().map(|x| (x.clone(), x.len())).collect();
+
+    group.bench_function(
+        BenchmarkId::new("get", BURST_MAP_STR),
+        |b| {
+            b.iter(|| text.iter().filter_map(|x| burst_map.get(x)).sum::<usize>());
+        },
+    );
+
+    group.bench_function(
+        BenchmarkId::new("get", BTREE_MAP_STR),
+        |b| {
+            b.iter(|| text.iter().filter_map(|x| btree_map.get(x)).sum::<usize>());
+        },
+    );
+
+    group.bench_function(
+        BenchmarkId::new("get", HASH_MAP_STR),
+        |b| {
+            b.iter(|| text.iter().filter_map(|x| hash_map.get(x)).sum::<usize>());
+        },
+    );
+
+    group.bench_function(
+        BenchmarkId::new("ordered-scan", BURST_MAP_STR),
+        |b| {
+            b.iter(|| burst_map.values().sum::<usize>());
+        },
+    );
+
+    group.bench_function(
+        BenchmarkId::new("ordered-scan", BTREE_MAP_STR),
+        |b| {
+            b.iter(|| btree_map.values().sum::<usize>());
+        },
+    );
+
+    group.bench_function(
+        BenchmarkId::new("ordered-scan", HASH_MAP_STR),
+        |b| {
+            b.iter(|| {
+                let mut entries: Vec<_> = hash_map.iter().collect();
+                entries.sort_unstable();
+                entries
+            });
+        },
+    );
+}
+
+pub fn bench_word_count(c: &mut Criterion, allocator: &str) {
+    let text = read_file("data/eng_news_2020_1M/eng_news_2020_1M-sentences.txt", false);
+
+    let name = format!("{}-compare-word-count-english", allocator);
+
+    let mut group = c.benchmark_group(name);
+
+    group.throughput(Throughput::Elements(text.len() as u64));
+
+    group.bench_function(
+        BenchmarkId::new("most-common", BURST_COUNTER_STR),
+        |b| {
+            b.iter(|| {
+                let counter: BurstCounter<&str> = text.iter().map(String::as_str).collect();
+                counter.most_common(100).len()
+            });
+        },
+    );
+
+    group.bench_function(
+        BenchmarkId::new("most-common", PAR_BURST_COUNTER_STR),
+        |b| {
+            b.iter(|| {
+                let words = text.iter().map(String::as_str).collect();
+                let counter = BurstCounter::par_from_vec(words, &COLLECTION_CONFIG);
+                counter.most_common(100).len()
+            });
+        },
+    );
+
+    group.bench_function(
+        BenchmarkId::new("most-common", HASH_MAP_STR),
+        |b| {
+            b.iter(|| {
+                let mut counts = HashMap::new();
+                for x in &text {
+                    *counts.entry(x.as_str()).or_insert(0usize) += 1;
+                }
+
+                let mut counts: Vec<_> = counts.into_iter().collect();
+                counts.sort_unstable_by(|lhs, rhs| rhs.1.cmp(&lhs.1).then(lhs.0.cmp(rhs.0)));
+                counts.truncate(100);
+                counts.len()
+            });
+        },
+    );
+
+    group.bench_function(
+        BenchmarkId::new("sorted-counts", BURST_COUNTER_STR),
+        |b| {
+            b.iter(|| {
+                let counter: BurstCounter<&str> = text.iter().map(String::as_str).collect();
+                counter.into_sorted_vec()
+            });
+        },
+    );
+
+    group.bench_function(
+        BenchmarkId::new("sorted-counts", HASH_MAP_STR),
+        |b| {
+            b.iter(|| {
+                let mut counts = HashMap::new();
+                for x in &text {
+                    *counts.entry(x.as_str()).or_insert(0usize) += 1;
+                }
+
+                let mut counts: Vec<_> = counts.into_iter().collect();
+                counts.sort_unstable();
+                counts
+            });
+        },
+    );
+}
+
+pub fn bench_batches(c: &mut Criterion, allocator: &str) {
+    const BATCH_SIZE: usize = 1_000;
+
+    let text = read_file_alpha("data/eng_news_2020_1M/eng_news_2020_1M-sentences.txt", false);
+
+    let name = format!("{}-compare-batches-english", allocator);
+
+    let mut group = c.benchmark_group(name);
+
+    group.throughput(Throughput::Elements(text.len() as u64));
+
+    group.bench_function(
+        BURST_STR,
+        |b| {
+            b.iter(|| {
+                for batch in text.chunks(BATCH_SIZE) {
+                    burstsort(&mut batch.to_vec(), &ASCII_CONFIG);
+                }
+            });
+        },
+    );
+
+    group.bench_function(
+        BURST_SORTER_STR,
+        |b| {
+            let mut sorter = BurstSorter::new(&ASCII_CONFIG);
+
+            b.iter(|| {
+                for batch in text.chunks(BATCH_SIZE) {
+                    sorter.sort(&mut batch.to_vec());
+                }
+            });
+        },
+    );
+
+    group.bench_function(
+        STD_UNSTABLE_STR,
+        |b| {
+            b.iter(|| {
+                for batch in text.chunks(BATCH_SIZE) {
+                    batch.to_vec().sort_unstable();
+                }
+            });
+        },
+    );
+}
+
 pub fn bench_random_count(c: &mut Criterion, allocator: &str) {
     const STEP_SIZE: usize = 25_000;
 
@@ -186,6 +417,27 @@ fn bench_with_text(c: &mut Criterion, param: &str, allocator: &str, text: Vec<St
         },
     );
 
+    group.bench_function(
+        ARENA_BURST_STR,
+        |b| {
+            b.iter(|| arena_burstsort(&mut text.clone(), &ASCII_CONFIG));
+        },
+    );
+
+    group.bench_function(
+        PAR_BURST_REFS_STR,
+        |b| {
+            b.iter(|| par_burstsort_refs(&text, &ASCII_CONFIG));
+        },
+    );
+
+    group.bench_function(
+        BURST_REFS_STR,
+        |b| {
+            b.iter(|| burstsort_refs(&text, &ASCII_CONFIG));
+        },
+    );
+
     group.bench_function(
         STD_UNSTABLE_STR,
         |b| {
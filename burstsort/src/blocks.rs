@@ -0,0 +1,110 @@
+//! Bucket storage made of chained blocks, which grows without copying its items.
+
+use std::iter::Flatten;
+use std::mem;
+use std::vec;
+
+/// Size of the first block, when no initial capacity is configured.
+const MIN_BLOCK: usize = 4;
+
+/// Bucket which stores its items in a chain of blocks.
+///
+/// Each new block is twice the size of the last, so a bucket of `n` items is made of `log(n)`
+/// blocks. Filled blocks are never reallocated, so appending never moves the items already
+/// stored, and the items are drained in insertion order.
+#[derive(Clone)]
+pub struct Blocks<T> {
+    blocks: Vec<Vec<T>>,
+    len: usize,
+    capacity: usize,
+}
+
+impl<T> Blocks<T> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of items the blocks can hold, to track the memory used by the bucket.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.blocks.iter().flatten()
+    }
+
+    /// Appends the item, starting a new block if the last one is full.
+    ///
+    /// `first` is the size of the first block of an empty bucket.
+    pub fn push(&mut self, item: T, first: usize) {
+        match self.blocks.last_mut() {
+            Some(block) if block.len() < block.capacity() => block.push(item),
+            last => {
+                let size = last.map_or(first, |x| x.capacity() * 2).max(MIN_BLOCK);
+                let mut block = Vec::with_capacity(size);
+
+                block.push(item);
+                self.capacity += block.capacity();
+                self.blocks.push(block);
+            }
+        }
+
+        self.len += 1;
+    }
+
+    /// Moves every block of another bucket to the end of this one.
+    pub fn append(&mut self, other: &mut Self) {
+        self.len += mem::replace(&mut other.len, 0);
+        self.capacity += mem::replace(&mut other.capacity, 0);
+        self.blocks.append(&mut other.blocks);
+    }
+
+    /// Collects the items into a single vector, in order.
+    pub fn into_vec(mut self) -> Vec<T> {
+        if self.blocks.len() == 1 {
+            return self.blocks.pop().unwrap();
+        }
+
+        let mut list = Vec::with_capacity(self.len);
+
+        for mut block in self.blocks {
+            list.append(&mut block);
+        }
+
+        list
+    }
+}
+
+impl<T> Default for Blocks<T> {
+    fn default() -> Self {
+        Self {
+            blocks: Vec::new(),
+            len: 0,
+            capacity: 0,
+        }
+    }
+}
+
+impl<T> From<Vec<T>> for Blocks<T> {
+    /// Wraps a vector as a single block, without copying it.
+    fn from(list: Vec<T>) -> Self {
+        Self {
+            len: list.len(),
+            capacity: list.capacity(),
+            blocks: vec![list],
+        }
+    }
+}
+
+impl<T> IntoIterator for Blocks<T> {
+    type Item = T;
+    type IntoIter = Flatten<vec::IntoIter<Vec<T>>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.blocks.into_iter().flatten()
+    }
+}
@@ -0,0 +1,249 @@
+use std::borrow::Borrow;
+use std::cmp::Reverse;
+use std::fmt::{self, Debug, Formatter};
+use std::iter::FromIterator;
+
+#[cfg(feature = "parallelization")]
+use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+
+use crate::COLLECTION_CONFIG;
+use crate::trie::{self, BurstConfig, EntryKeys, Trie};
+
+/// Counts occurrences of keys, built on a burst trie.
+///
+/// Each distinct key is stored once along with its count, so memory grows with the vocabulary
+/// rather than with the input. Counts can be read back in key order, or by frequency.
+///
+/// # Type Parameters
+/// See [burstsort](crate::burstsort) for details on the type parameters.
+///
+/// # Examples
+/// ```
+/// use burstsort::BurstCounter;
+///
+/// let counter: BurstCounter<_> = "the cat and the hat".split(' ').collect();
+///
+/// assert_eq!(2, counter.count("the"));
+/// assert_eq!(vec![(&"the", 2), (&"and", 1)], counter.most_common(2));
+/// assert_eq!(vec![(&"and", 1), (&"cat", 1), (&"hat", 1), (&"the", 2)],
+///            counter.iter().collect::<Vec<_>>());
+/// ```
+#[derive(Clone)]
+pub struct BurstCounter<T, C = &'static BurstConfig, I = u8> {
+    root: Trie<C, (T, usize), I, EntryKeys>,
+    len: usize,
+}
+
+impl<T> BurstCounter<T>
+    where T: AsRef<[u8]>
+{
+    /// Creates an empty counter using [COLLECTION_CONFIG].
+    pub fn new() -> Self {
+        Self::with_config(&COLLECTION_CONFIG)
+    }
+}
+
+impl<T, C, I> BurstCounter<T, C, I>
+    where T: AsRef<[I]>,
+          C: Borrow<BurstConfig> + Clone,
+          I: Into<usize> + Clone + Ord
+{
+    pub fn with_config(config: C) -> Self {
+        Self {
+            root: Trie::new(config, EntryKeys),
+            len: 0,
+        }
+    }
+
+    /// Adds one occurrence of the key.
+    pub fn insert(&mut self, key: T) {
+        self.insert_count(key, 1);
+    }
+
+    /// Adds `count` occurrences of the key.
+    pub fn insert_count(&mut self, key: T, count: usize) {
+        if let Some((_, existing)) = self.root.get_mut(key.as_ref()) {
+            *existing += count;
+        } else {
+            self.root.insert_ordered((key, count));
+            self.len += 1;
+        }
+    }
+
+    /// Returns the number of occurrences of the key.
+    pub fn count<Q>(&self, key: &Q) -> usize
+        where Q: AsRef<[I]> + ?Sized
+    {
+        self.root.get(key.as_ref()).map_or(0, |&(_, count)| count)
+    }
+
+    /// Returns the number of distinct keys.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Approximate bytes allocated by the counter, as counted for [BurstConfig::max_memory].
+    pub fn memory_used(&self) -> usize {
+        self.root.memory_used()
+    }
+
+    /// Iterates over the distinct keys in order, along with their counts.
+    pub fn iter(&self) -> Iter<'_, T, C, I> {
+        Iter {
+            inner: self.root.iter(),
+            len: self.len,
+        }
+    }
+
+    /// Returns the `n` most common keys along with their counts, from most to least common.
+    ///
+    /// Keys with equal counts are returned in key order.
+    pub fn most_common(&self, n: usize) -> Vec<(&T, usize)> {
+        let mut entries: Vec<_> = self.iter().collect();
+
+        // stable sort, so ties stay in key order
+        entries.sort_by_key(|&(_, count)| Reverse(count));
+        entries.truncate(n);
+
+        entries
+    }
+
+    /// Moves every key of `other` into this counter, adding up the counts of keys found in both.
+    ///
+    /// The tries are merged node by node. Both counters should use the same config.
+    pub fn append(&mut self, other: &mut Self) {
+        self.root.append_with(&mut other.root, &mut |(_, count), (_, more)| *count += more);
+        self.len = self.root.count();
+        other.len = 0;
+    }
+
+    /// Converts the counter into its distinct keys in order, along with their counts.
+    pub fn into_sorted_vec(mut self) -> Vec<(T, usize)> {
+        let mut sorted = Vec::with_capacity(self.len);
+
+        self.root.merge_ordered(&mut sorted);
+
+        sorted
+    }
+}
+
+#[cfg(feature = "parallelization")]
+impl<T, C, I> BurstCounter<T, C, I>
+    where T: AsRef<[I]> + Send,
+          C: Borrow<BurstConfig> + Clone + Send + Sync,
+          I: Into<usize> + Clone + Ord + Send + Sync
+{
+    /// Counts the keys in parallel.
+    ///
+    /// Each thread counts part of the data into its own trie, and the tries are then merged node
+    /// by node.
+    ///
+    /// # Examples
+    /// ```
+    /// use burstsort::BurstCounter;
+    ///
+    /// let words = "the cat and the hat".split(' ').collect();
+    /// let counter = BurstCounter::par_from_vec(words, &burstsort::COLLECTION_CONFIG);
+    ///
+    /// assert_eq!(vec![(&"the", 2), (&"and", 1)], counter.most_common(2));
+    /// ```
+    pub fn par_from_vec(data: Vec<T>, config: C) -> Self {
+        data.into_par_iter()
+            .fold(|| Self::with_config(config.clone()), |mut counter, x| {
+                counter.insert(x);
+                counter
+            })
+            .reduce(|| Self::with_config(config.clone()), |mut counter, mut other| {
+                counter.append(&mut other);
+                counter
+            })
+    }
+}
+
+impl<T> Default for BurstCounter<T>
+    where T: AsRef<[u8]>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, C, I> Debug for BurstCounter<T, C, I>
+    where T: AsRef<[I]> + Debug,
+          C: Borrow<BurstConfig> + Clone,
+          I: Into<usize> + Clone + Ord
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<T, C, I> Extend<T> for BurstCounter<T, C, I>
+    where T: AsRef<[I]>,
+          C: Borrow<BurstConfig> + Clone,
+          I: Into<usize> + Clone + Ord
+{
+    fn extend<It: IntoIterator<Item=T>>(&mut self, iter: It) {
+        for x in iter {
+            self.insert(x);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for BurstCounter<T>
+    where T: AsRef<[u8]>
+{
+    fn from_iter<It: IntoIterator<Item=T>>(iter: It) -> Self {
+        let mut counter = Self::new();
+        counter.extend(iter);
+        counter
+    }
+}
+
+impl<'a, T, C, I> IntoIterator for &'a BurstCounter<T, C, I>
+    where T: AsRef<[I]>,
+          C: Borrow<BurstConfig> + Clone,
+          I: Into<usize> + Clone + Ord
+{
+    type Item = (&'a T, usize);
+    type IntoIter = Iter<'a, T, C, I>;
+
+    fn into_iter(self) -> Iter<'a, T, C, I> {
+        self.iter()
+    }
+}
+
+/// Iterator over the keys of a [BurstCounter] in order, along with their counts.
+pub struct Iter<'a, T, C = &'static BurstConfig, I = u8> {
+    inner: trie::Iter<'a, C, (T, usize), I, EntryKeys>,
+    len: usize,
+}
+
+impl<'a, T, C, I> Clone for Iter<'a, T, C, I> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<'a, T, C, I> Iterator for Iter<'a, T, C, I> {
+    type Item = (&'a T, usize);
+
+    fn next(&mut self) -> Option<(&'a T, usize)> {
+        let (key, count) = self.inner.next()?;
+        self.len -= 1;
+        Some((key, *count))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T, C, I> ExactSizeIterator for Iter<'a, T, C, I> {}
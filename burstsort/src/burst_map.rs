@@ -0,0 +1,394 @@
+use std::borrow::Borrow;
+use std::fmt::{self, Debug, Formatter};
+use std::iter::FromIterator;
+use std::mem;
+use std::ops::RangeBounds;
+
+use crate::COLLECTION_CONFIG;
+use crate::trie::{self, BurstConfig, EntryKeys, to_owned_bound, Trie};
+
+/// Ordered map built on a burst trie.
+///
+/// Lookups descend the trie by key and then binary search a single sorted bucket, and iteration
+/// is in key order.
+///
+/// # Type Parameters
+/// See [burstsort](crate::burstsort) for details on the type parameters.
+///
+/// # Examples
+/// ```
+/// use burstsort::BurstMap;
+///
+/// let mut map = BurstMap::new();
+///
+/// map.insert("pear", 3);
+/// map.insert("apple", 1);
+/// *map.entry("orange").or_insert(0) += 2;
+///
+/// assert_eq!(Some(&1), map.get("apple"));
+/// assert_eq!(vec![(&"apple", &1), (&"orange", &2), (&"pear", &3)],
+///            map.iter().collect::<Vec<_>>());
+/// ```
+#[derive(Clone)]
+pub struct BurstMap<K, V, C = &'static BurstConfig, I = u8> {
+    root: Trie<C, (K, V), I, EntryKeys>,
+    len: usize,
+}
+
+impl<K, V> BurstMap<K, V>
+    where K: AsRef<[u8]>
+{
+    /// Creates an empty map using [COLLECTION_CONFIG].
+    pub fn new() -> Self {
+        Self::with_config(&COLLECTION_CONFIG)
+    }
+}
+
+impl<K, V, C, I> BurstMap<K, V, C, I>
+    where K: AsRef<[I]>,
+          C: Borrow<BurstConfig> + Clone,
+          I: Into<usize> + Clone + Ord
+{
+    pub fn with_config(config: C) -> Self {
+        Self {
+            root: Trie::new(config, EntryKeys),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Approximate bytes allocated by the map, as counted for [BurstConfig::max_memory].
+    pub fn memory_used(&self) -> usize {
+        self.root.memory_used()
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+        where Q: AsRef<[I]> + ?Sized
+    {
+        self.root.get(key.as_ref()).is_some()
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+        where Q: AsRef<[I]> + ?Sized
+    {
+        self.root.get(key.as_ref()).map(|(_, v)| v)
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+        where Q: AsRef<[I]> + ?Sized
+    {
+        self.root.get(key.as_ref()).map(|(k, v)| (k, v))
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+        where Q: AsRef<[I]> + ?Sized
+    {
+        self.root.get_mut(key.as_ref()).map(|(_, v)| v)
+    }
+
+    /// Inserts a key-value pair into the map, returning the previous value for the key if there
+    /// was one. The key itself is not updated if it was already present.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some((_, v)) = self.root.get_mut(key.as_ref()) {
+            return Some(mem::replace(v, value));
+        }
+
+        self.root.insert_ordered((key, value));
+        self.len += 1;
+
+        None
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+        where Q: AsRef<[I]> + ?Sized
+    {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+        where Q: AsRef<[I]> + ?Sized
+    {
+        let entry = self.root.remove(key.as_ref())?;
+        self.len -= 1;
+        Some(entry)
+    }
+
+    /// Keeps only the entries for which the predicate returns true.
+    ///
+    /// Parts of the trie left mostly empty are collapsed back into buckets.
+    pub fn retain<F>(&mut self, mut f: F)
+        where F: FnMut(&K, &mut V) -> bool
+    {
+        self.root.retain(&mut |(k, v)| f(k, v));
+        self.len = self.root.count();
+    }
+
+    /// Removes the entries with keys within the range, returning them in key order.
+    pub fn drain_range<Q, R>(&mut self, range: R) -> Vec<(K, V)>
+        where Q: AsRef<[I]>,
+              R: RangeBounds<Q>
+    {
+        let mut drained = Vec::new();
+
+        self.root.drain_range(to_owned_bound(range.start_bound()),
+                              to_owned_bound(range.end_bound()), &mut drained);
+        self.len -= drained.len();
+
+        drained
+    }
+
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, C, I> {
+        if self.contains_key(&key) {
+            let entry = self.root.get_mut(key.as_ref()).unwrap();
+            Entry::Occupied(OccupiedEntry { entry })
+        } else {
+            Entry::Vacant(VacantEntry { map: self, key })
+        }
+    }
+
+    /// Iterates over the entries of the map in key order.
+    pub fn iter(&self) -> Iter<'_, K, V, C, I> {
+        Iter {
+            inner: self.root.iter(),
+            len: self.len,
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item=&K> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item=&V> {
+        self.iter().map(|(_, v)| v)
+    }
+
+    /// Iterates over the entries of the map with keys inside the range, in key order.
+    ///
+    /// Parts of the trie outside of the range are skipped without being visited.
+    ///
+    /// # Examples
+    /// ```
+    /// use burstsort::BurstMap;
+    ///
+    /// let map: BurstMap<_, _> = vec![("apple", 1), ("banana", 2), ("cherry", 3)]
+    ///     .into_iter()
+    ///     .collect();
+    ///
+    /// let keys: Vec<_> = map.range("b".."c").map(|(k, _)| *k).collect();
+    ///
+    /// assert_eq!(vec!["banana"], keys);
+    /// ```
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V, C, I>
+        where Q: AsRef<[I]>,
+              R: RangeBounds<Q>
+    {
+        Range {
+            inner: self.root.range(to_owned_bound(range.start_bound()),
+                                   to_owned_bound(range.end_bound())),
+        }
+    }
+}
+
+impl<K, V> Default for BurstMap<K, V>
+    where K: AsRef<[u8]>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, C, I> Debug for BurstMap<K, V, C, I>
+    where K: AsRef<[I]> + Debug,
+          V: Debug,
+          C: Borrow<BurstConfig> + Clone,
+          I: Into<usize> + Clone + Ord
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V, C, I> Extend<(K, V)> for BurstMap<K, V, C, I>
+    where K: AsRef<[I]>,
+          C: Borrow<BurstConfig> + Clone,
+          I: Into<usize> + Clone + Ord
+{
+    fn extend<It: IntoIterator<Item=(K, V)>>(&mut self, iter: It) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for BurstMap<K, V>
+    where K: AsRef<[u8]>
+{
+    fn from_iter<It: IntoIterator<Item=(K, V)>>(iter: It) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<'a, K, V, C, I> IntoIterator for &'a BurstMap<K, V, C, I>
+    where K: AsRef<[I]>,
+          C: Borrow<BurstConfig> + Clone,
+          I: Into<usize> + Clone + Ord
+{
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V, C, I>;
+
+    fn into_iter(self) -> Iter<'a, K, V, C, I> {
+        self.iter()
+    }
+}
+
+/// View into a single entry of a [BurstMap], created by [BurstMap::entry].
+pub enum Entry<'a, K, V, C = &'static BurstConfig, I = u8> {
+    Occupied(OccupiedEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V, C, I>),
+}
+
+pub struct OccupiedEntry<'a, K, V> {
+    entry: &'a mut (K, V),
+}
+
+pub struct VacantEntry<'a, K, V, C = &'static BurstConfig, I = u8> {
+    map: &'a mut BurstMap<K, V, C, I>,
+    key: K,
+}
+
+impl<'a, K, V, C, I> Entry<'a, K, V, C, I>
+    where K: AsRef<[I]>,
+          C: Borrow<BurstConfig> + Clone,
+          I: Into<usize> + Clone + Ord
+{
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+        where V: Default
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+
+        self
+    }
+}
+
+impl<'a, K, V> OccupiedEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.entry.0
+    }
+
+    pub fn get(&self) -> &V {
+        &self.entry.1
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.entry.1
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.entry.1
+    }
+
+    /// Replaces the value of the entry, returning the old value.
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(&mut self.entry.1, value)
+    }
+}
+
+impl<'a, K, V, C, I> VacantEntry<'a, K, V, C, I>
+    where K: AsRef<[I]>,
+          C: Borrow<BurstConfig> + Clone,
+          I: Into<usize> + Clone + Ord
+{
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        self.map.len += 1;
+        &mut self.map.root.insert_ordered((self.key, value)).1
+    }
+}
+
+/// Iterator over the entries of a [BurstMap] in key order.
+pub struct Iter<'a, K, V, C = &'static BurstConfig, I = u8> {
+    inner: trie::Iter<'a, C, (K, V), I, EntryKeys>,
+    len: usize,
+}
+
+impl<'a, K, V, C, I> Clone for Iter<'a, K, V, C, I> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<'a, K, V, C, I> Iterator for Iter<'a, K, V, C, I> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        let (k, v) = self.inner.next()?;
+        self.len -= 1;
+        Some((k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V, C, I> ExactSizeIterator for Iter<'a, K, V, C, I> {}
+
+/// Iterator over a range of the entries of a [BurstMap] in key order, created by
+/// [BurstMap::range].
+pub struct Range<'a, K, V, C = &'static BurstConfig, I = u8> {
+    inner: trie::Range<'a, C, (K, V), I, EntryKeys>,
+}
+
+impl<'a, K, V, C, I> Iterator for Range<'a, K, V, C, I>
+    where K: AsRef<[I]>,
+          I: Into<usize> + Clone + Ord
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        self.inner.next().map(|(k, v)| (k, v))
+    }
+}
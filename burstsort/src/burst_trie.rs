@@ -0,0 +1,436 @@
+use std::borrow::Borrow;
+use std::cmp::Reverse;
+use std::fmt::{self, Debug, Formatter};
+use std::iter::FromIterator;
+use std::ops::RangeBounds;
+
+use crate::COLLECTION_CONFIG;
+use crate::trie::{self, BurstConfig, SetOp, to_owned_bound, Trie};
+
+/// Sorted multiset built on a burst trie.
+///
+/// Items can be inserted incrementally, and read back in order at any point. Unlike the one-shot
+/// sorts, every bucket of the trie is kept sorted, so smaller burst limits such as the one in
+/// [COLLECTION_CONFIG] work best.
+///
+/// # Type Parameters
+/// See [burstsort](crate::burstsort) for details on the type parameters.
+///
+/// # Examples
+/// ```
+/// use burstsort::BurstTrie;
+///
+/// let mut trie: BurstTrie<String> = vec!["pear", "apple"].into_iter().map(String::from).collect();
+///
+/// trie.insert(String::from("orange"));
+///
+/// assert_eq!(3, trie.len());
+/// assert_eq!(vec!["apple", "orange", "pear"], trie.iter().collect::<Vec<_>>());
+/// ```
+#[derive(Clone)]
+pub struct BurstTrie<T, C = &'static BurstConfig, I = u8> {
+    root: Trie<C, T, I>,
+    len: usize,
+}
+
+impl<T> BurstTrie<T>
+    where T: AsRef<[u8]>
+{
+    /// Creates an empty trie using [COLLECTION_CONFIG].
+    pub fn new() -> Self {
+        Self::with_config(&COLLECTION_CONFIG)
+    }
+}
+
+impl<T, C, I> BurstTrie<T, C, I>
+    where T: AsRef<[I]>,
+          C: Borrow<BurstConfig> + Clone,
+          I: Into<usize> + Clone + Ord
+{
+    pub fn with_config(config: C) -> Self {
+        Self {
+            root: Trie::new(config, trie::ItemKeys),
+            len: 0,
+        }
+    }
+
+    pub fn insert(&mut self, item: T) {
+        self.root.insert_ordered(item);
+        self.len += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Approximate bytes allocated by the trie, as counted for [BurstConfig::max_memory].
+    pub fn memory_used(&self) -> usize {
+        self.root.memory_used()
+    }
+
+    /// Iterates over the items of the trie in order.
+    pub fn iter(&self) -> Iter<'_, T, C, I> {
+        Iter {
+            inner: self.root.iter(),
+            len: self.len,
+        }
+    }
+
+    /// Iterates over the items of the trie with keys starting with the prefix, in order.
+    ///
+    /// # Examples
+    /// ```
+    /// use burstsort::BurstTrie;
+    ///
+    /// let trie: BurstTrie<_> = vec!["apple", "banana", "applet", "app"].into_iter().collect();
+    ///
+    /// assert_eq!(vec![&"app", &"apple", &"applet"], trie.iter_prefix("app").collect::<Vec<_>>());
+    /// ```
+    pub fn iter_prefix<Q>(&self, prefix: &Q) -> Prefix<'_, T, C, I>
+        where Q: AsRef<[I]> + ?Sized
+    {
+        Prefix {
+            inner: self.root.iter_prefix(prefix.as_ref()),
+        }
+    }
+
+    /// Counts the items of the trie with keys starting with the prefix.
+    pub fn count_prefix<Q>(&self, prefix: &Q) -> usize
+        where Q: AsRef<[I]> + ?Sized
+    {
+        self.root.count_prefix(prefix.as_ref())
+    }
+
+    /// Finds the `k` most frequent keys starting with the prefix, along with their counts.
+    ///
+    /// Results are ordered by decreasing count, and then by key. Only the part of the trie holding
+    /// keys with the prefix is scanned.
+    ///
+    /// # Examples
+    /// ```
+    /// use burstsort::BurstTrie;
+    ///
+    /// let trie: BurstTrie<_> = vec!["app", "apple", "apply", "apple", "bat"]
+    ///     .into_iter()
+    ///     .collect();
+    ///
+    /// assert_eq!(vec![(&"apple", 2), (&"app", 1)], trie.most_common_with_prefix("app", 2));
+    /// ```
+    pub fn most_common_with_prefix<Q>(&self, prefix: &Q, k: usize) -> Vec<(&T, usize)>
+        where Q: AsRef<[I]> + ?Sized
+    {
+        let mut runs: Vec<(&T, usize)> = Vec::new();
+
+        // equal keys are adjacent, so each run of them is one distinct key
+        for x in self.iter_prefix(prefix) {
+            match runs.last_mut() {
+                Some((item, count)) if item.as_ref() == x.as_ref() => *count += 1,
+                _ => runs.push((x, 1)),
+            }
+        }
+
+        // stable sort, so ties stay in key order
+        runs.sort_by_key(|&(_, count)| Reverse(count));
+        runs.truncate(k);
+
+        runs
+    }
+
+    /// Iterates over the items of the trie with keys inside the range, in order.
+    ///
+    /// Whole subtables of the trie outside of the range are skipped without being visited.
+    ///
+    /// # Examples
+    /// ```
+    /// use burstsort::BurstTrie;
+    ///
+    /// let trie: BurstTrie<_> = vec!["apple", "banana", "cherry"].into_iter().collect();
+    ///
+    /// assert_eq!(vec![&"banana", &"cherry"], trie.range("b"..).collect::<Vec<_>>());
+    /// ```
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, T, C, I>
+        where Q: AsRef<[I]>,
+              R: RangeBounds<Q>
+    {
+        Range {
+            inner: self.root.range(to_owned_bound(range.start_bound()),
+                                   to_owned_bound(range.end_bound())),
+        }
+    }
+
+    /// Finds the item with the longest key which is a prefix of the given key.
+    ///
+    /// This is the lookup used by routing tables, where the most specific matching route wins.
+    ///
+    /// # Examples
+    /// ```
+    /// use burstsort::BurstTrie;
+    ///
+    /// let routes: BurstTrie<_> = vec!["/", "/api/", "/api/v1/"].into_iter().collect();
+    ///
+    /// assert_eq!(Some(&"/api/"), routes.longest_prefix_match("/api/v2/users"));
+    /// assert_eq!(Some(&"/"), routes.longest_prefix_match("/static/index.html"));
+    /// ```
+    pub fn longest_prefix_match<Q>(&self, key: &Q) -> Option<&T>
+        where Q: AsRef<[I]> + ?Sized
+    {
+        self.root.longest_prefix_match(key.as_ref())
+    }
+
+    /// Removes one item with the given key, if any.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<T>
+        where Q: AsRef<[I]> + ?Sized
+    {
+        let item = self.root.remove(key.as_ref())?;
+        self.len -= 1;
+        Some(item)
+    }
+
+    /// Keeps only the items for which the predicate returns true.
+    ///
+    /// Parts of the trie left mostly empty are collapsed back into buckets.
+    pub fn retain<F>(&mut self, mut f: F)
+        where F: FnMut(&T) -> bool
+    {
+        self.root.retain(&mut |x| f(x));
+        self.len = self.root.count();
+    }
+
+    /// Removes the items within the range, returning them in order.
+    ///
+    /// # Examples
+    /// ```
+    /// use burstsort::BurstTrie;
+    ///
+    /// let mut trie: BurstTrie<_> = vec!["apple", "banana", "cherry", "date"]
+    ///     .into_iter()
+    ///     .collect();
+    ///
+    /// assert_eq!(vec!["banana", "cherry"], trie.drain_range("b".."d"));
+    /// assert_eq!(vec![&"apple", &"date"], trie.iter().collect::<Vec<_>>());
+    /// ```
+    pub fn drain_range<Q, R>(&mut self, range: R) -> Vec<T>
+        where Q: AsRef<[I]>,
+              R: RangeBounds<Q>
+    {
+        let mut drained = Vec::new();
+
+        self.root.drain_range(to_owned_bound(range.start_bound()),
+                              to_owned_bound(range.end_bound()), &mut drained);
+        self.len -= drained.len();
+
+        drained
+    }
+
+    /// Moves every item of `other` into this trie, leaving it empty.
+    ///
+    /// The tries are merged node by node rather than item by item, so this is much cheaper than
+    /// re-inserting the items when both are large. Both tries should use the same config.
+    ///
+    /// # Examples
+    /// ```
+    /// use burstsort::BurstTrie;
+    ///
+    /// let mut a: BurstTrie<_> = vec!["cherry", "apple"].into_iter().collect();
+    /// let mut b: BurstTrie<_> = vec!["banana", "apple"].into_iter().collect();
+    ///
+    /// a.append(&mut b);
+    ///
+    /// assert!(b.is_empty());
+    /// assert_eq!(vec!["apple", "apple", "banana", "cherry"], a.into_sorted_vec());
+    /// ```
+    pub fn append(&mut self, other: &mut Self) {
+        self.root.append(&mut other.root, true);
+        self.len += other.len;
+        other.len = 0;
+    }
+
+    /// Returns the distinct keys of either trie, in order.
+    ///
+    /// When both tries have a key, the item from this trie is kept.
+    ///
+    /// # Examples
+    /// ```
+    /// use burstsort::BurstTrie;
+    ///
+    /// let a: BurstTrie<_> = vec!["cherry", "apple", "apple"].into_iter().collect();
+    /// let b: BurstTrie<_> = vec!["banana", "apple"].into_iter().collect();
+    ///
+    /// assert_eq!(vec!["apple", "banana", "cherry"], a.union(b));
+    /// ```
+    pub fn union(self, other: Self) -> Vec<T> {
+        self.set_op(other, SetOp::Union)
+    }
+
+    /// Returns the distinct keys found in both tries, in order.
+    ///
+    /// # Examples
+    /// ```
+    /// use burstsort::BurstTrie;
+    ///
+    /// let a: BurstTrie<_> = vec!["cherry", "apple", "apple"].into_iter().collect();
+    /// let b: BurstTrie<_> = vec!["banana", "apple"].into_iter().collect();
+    ///
+    /// assert_eq!(vec!["apple"], a.intersection(b));
+    /// ```
+    pub fn intersection(self, other: Self) -> Vec<T> {
+        self.set_op(other, SetOp::Intersection)
+    }
+
+    /// Returns the distinct keys found in this trie but not in `other`, in order.
+    ///
+    /// # Examples
+    /// ```
+    /// use burstsort::BurstTrie;
+    ///
+    /// let a: BurstTrie<_> = vec!["cherry", "apple", "apple"].into_iter().collect();
+    /// let b: BurstTrie<_> = vec!["banana", "apple"].into_iter().collect();
+    ///
+    /// assert_eq!(vec!["cherry"], a.difference(b));
+    /// ```
+    pub fn difference(self, other: Self) -> Vec<T> {
+        self.set_op(other, SetOp::Difference)
+    }
+
+    fn set_op(mut self, mut other: Self, op: SetOp) -> Vec<T> {
+        let mut result = Vec::new();
+
+        self.root.set_op(&mut other.root, op, &mut result);
+
+        result
+    }
+
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut sorted = Vec::with_capacity(self.len);
+
+        self.root.merge_ordered(&mut sorted);
+
+        sorted
+    }
+}
+
+impl<T> Default for BurstTrie<T>
+    where T: AsRef<[u8]>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, C, I> Debug for BurstTrie<T, C, I>
+    where T: AsRef<[I]> + Debug,
+          C: Borrow<BurstConfig> + Clone,
+          I: Into<usize> + Clone + Ord
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T, C, I> Extend<T> for BurstTrie<T, C, I>
+    where T: AsRef<[I]>,
+          C: Borrow<BurstConfig> + Clone,
+          I: Into<usize> + Clone + Ord
+{
+    fn extend<It: IntoIterator<Item=T>>(&mut self, iter: It) {
+        for x in iter {
+            self.insert(x);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for BurstTrie<T>
+    where T: AsRef<[u8]>
+{
+    fn from_iter<It: IntoIterator<Item=T>>(iter: It) -> Self {
+        let mut trie = Self::new();
+        trie.extend(iter);
+        trie
+    }
+}
+
+impl<'a, T, C, I> IntoIterator for &'a BurstTrie<T, C, I>
+    where T: AsRef<[I]>,
+          C: Borrow<BurstConfig> + Clone,
+          I: Into<usize> + Clone + Ord
+{
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, C, I>;
+
+    fn into_iter(self) -> Iter<'a, T, C, I> {
+        self.iter()
+    }
+}
+
+/// Iterator over the items of a [BurstTrie] in order.
+pub struct Iter<'a, T, C = &'static BurstConfig, I = u8> {
+    inner: trie::Iter<'a, C, T, I>,
+    len: usize,
+}
+
+impl<'a, T, C, I> Clone for Iter<'a, T, C, I> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<'a, T, C, I> Iterator for Iter<'a, T, C, I> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let next = self.inner.next()?;
+        self.len -= 1;
+        Some(next)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T, C, I> ExactSizeIterator for Iter<'a, T, C, I> {}
+
+/// Iterator over the items of a [BurstTrie] with a given prefix, created by
+/// [BurstTrie::iter_prefix].
+pub struct Prefix<'a, T, C = &'static BurstConfig, I = u8> {
+    inner: trie::Iter<'a, C, T, I>,
+}
+
+impl<'a, T, C, I> Clone for Prefix<'a, T, C, I> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, T, C, I> Iterator for Prefix<'a, T, C, I> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.inner.next()
+    }
+}
+
+/// Iterator over the items of a [BurstTrie] within a range, created by [BurstTrie::range].
+pub struct Range<'a, T, C = &'static BurstConfig, I = u8> {
+    inner: trie::Range<'a, C, T, I>,
+}
+
+impl<'a, T, C, I> Iterator for Range<'a, T, C, I>
+    where T: AsRef<[I]>,
+          I: Into<usize> + Clone + Ord
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.inner.next()
+    }
+}
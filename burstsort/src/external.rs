@@ -0,0 +1,346 @@
+//! External-memory burstsort, for inputs which do not fit in memory.
+
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::mem;
+use std::ops::Bound;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::burstsort;
+use crate::trie::{BurstConfig, ItemKeys, Trie};
+
+/// Gives every temporary directory and spill file a unique name.
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Burstsort for byte strings which may not fit in memory.
+///
+/// Items are inserted into a burst trie until it exceeds the memory budget. The largest buckets
+/// are then spilled to temporary files, keyed by the prefix shared by their items, and later
+/// items with a spilled prefix go straight to its file. Repeated keys which end at a trie node
+/// are spilled the same way, keyed by the whole key. Since trie prefixes partition the key
+/// space, each file is reloaded and sorted on its own at the end, with no merging between files.
+///
+/// The budget counts the bytes of each key plus the size of its [Vec], so it is approximate:
+/// node tables and allocator overhead are not included.
+///
+/// # Examples
+/// ```
+/// use burstsort::ExternalSorter;
+///
+/// let mut sorter = ExternalSorter::new(&burstsort::UTF8_CONFIG, 1 << 20)?;
+///
+/// for x in &["pear", "apple", "orange"] {
+///     sorter.insert(x.as_bytes().to_vec())?;
+/// }
+///
+/// let mut sorted = Vec::new();
+/// sorter.finish(|x| sorted.push(String::from_utf8(x).unwrap()))?;
+///
+/// assert_eq!(vec!["apple", "orange", "pear"], sorted);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct ExternalSorter<C = &'static BurstConfig> {
+    root: Trie<C, Vec<u8>, u8>,
+    config: C,
+    max_memory: usize,
+    /// Approximate bytes held by the trie and by items waiting to be written to spill files.
+    used: usize,
+    /// Value of `used` after the last spill. Should a spill fail to get back under the budget,
+    /// the next one waits for a quarter of the budget to be added, rather than every insert
+    /// walking the whole trie again.
+    spilled_to: usize,
+    /// Spilled buckets, keyed by the prefix all of their items share, and spilled matches, keyed
+    /// by the key all of their items have. Since only list nodes are spilled, the prefix of a
+    /// bucket is never a prefix of another key in here.
+    spills: BTreeMap<Vec<u8>, Spill>,
+    /// Only buckets at this level or deeper are spilled, so that a spilled bucket which is sorted
+    /// externally again can never be spilled whole.
+    min_level: usize,
+    dir: PathBuf,
+    /// Whether the directory was created by this sorter, and should be removed along with it.
+    owns_dir: bool,
+}
+
+impl<C> ExternalSorter<C>
+    where C: Borrow<BurstConfig> + Clone + Send + Sync
+{
+    /// Creates a sorter which spills to a new directory under [std::env::temp_dir].
+    pub fn new(config: C, max_memory: usize) -> io::Result<Self> {
+        let name = format!("burstsort-{}-{}", process::id(), next_id());
+        let dir = std::env::temp_dir().join(name);
+
+        fs::create_dir(&dir)?;
+
+        Ok(Self::with_dir(config, max_memory, dir, 1, true))
+    }
+
+    /// Creates a sorter which spills to files in an existing directory.
+    pub fn in_dir<P: Into<PathBuf>>(config: C, max_memory: usize, dir: P) -> Self {
+        Self::with_dir(config, max_memory, dir.into(), 1, false)
+    }
+
+    fn with_dir(config: C, max_memory: usize, dir: PathBuf, min_level: usize, owns_dir: bool)
+                -> Self {
+        Self {
+            root: Trie::new(config.clone(), ItemKeys),
+            config,
+            max_memory,
+            used: 0,
+            spilled_to: 0,
+            spills: BTreeMap::new(),
+            min_level,
+            dir,
+            owns_dir,
+        }
+    }
+
+    pub fn insert(&mut self, item: Vec<u8>) -> io::Result<()> {
+        self.used += weight(&item);
+
+        match self.spill_for(&item) {
+            Some(spill) => spill.pending.push(item),
+            None => self.root.insert(item),
+        }
+
+        if self.used > self.max_memory.max(self.spilled_to + self.max_memory / 4) {
+            self.spill()?;
+            self.spilled_to = self.used;
+        }
+
+        Ok(())
+    }
+
+    /// Approximate bytes held by the trie and by items waiting to be written to spill files,
+    /// which is kept under the budget.
+    pub fn memory_used(&self) -> usize {
+        self.used
+    }
+
+    /// Sorts every inserted item, passing them to `emit` in order.
+    ///
+    /// Spilled buckets are reloaded one at a time. A bucket which is larger than the budget is
+    /// itself sorted externally, spilling one level deeper.
+    pub fn finish<F: FnMut(Vec<u8>)>(mut self, mut emit: F) -> io::Result<()> {
+        self.finish_into(&mut emit)
+    }
+
+    fn finish_into<F: FnMut(Vec<u8>)>(&mut self, emit: &mut F) -> io::Result<()> {
+        let mut sorted = Vec::with_capacity(self.root.count());
+        self.root.merge(&mut sorted);
+
+        let mut sorted = sorted.into_iter().peekable();
+
+        for (prefix, spill) in mem::take(&mut self.spills) {
+            // items in memory never start with the prefix of a spilled bucket, nor have the key
+            // of spilled matches, so each of them goes either before or after every spilled item
+            while let Some(x) = sorted.next_if(|x| x[..] <= prefix[..]) {
+                emit(x);
+            }
+
+            self.sort_spill(&prefix, spill, emit)?;
+        }
+
+        sorted.for_each(emit);
+
+        Ok(())
+    }
+
+    fn sort_spill<F: FnMut(Vec<u8>)>(&self, prefix: &[u8], mut spill: Spill, emit: &mut F)
+                                     -> io::Result<()> {
+        spill.flush()?;
+
+        let mut reader = BufReader::new(File::open(&spill.path)?);
+
+        if spill.exact {
+            // every item is a copy of the key, so they are already in order
+            while let Some(x) = read_item(&mut reader)? {
+                emit(x);
+            }
+        } else if spill.size <= self.max_memory {
+            let mut items = Vec::new();
+
+            while let Some(x) = read_item(&mut reader)? {
+                items.push(x);
+            }
+
+            burstsort(&mut items, self.config.clone());
+            items.into_iter().for_each(&mut *emit);
+        } else {
+            let mut nested = Self::with_dir(self.config.clone(), self.max_memory,
+                                            self.dir.clone(), prefix.len() + 1, false);
+
+            while let Some(x) = read_item(&mut reader)? {
+                nested.insert(x)?;
+            }
+
+            nested.finish_into(emit)?;
+        }
+
+        fs::remove_file(&spill.path)
+    }
+
+    /// Finds the spilled bucket which the key belongs to, if any.
+    fn spill_for(&mut self, key: &[u8]) -> Option<&mut Spill> {
+        // since the prefixes of spilled buckets are prefixes of no other spilled key, only the
+        // closest one below the key can match
+        let (prefix, spill) = self.spills
+            .range_mut::<[u8], _>((Bound::Unbounded, Bound::Included(key)))
+            .next_back()?;
+
+        if key.starts_with(prefix) && (!spill.exact || key.len() == prefix.len()) {
+            Some(spill)
+        } else {
+            None
+        }
+    }
+
+    /// Writes out items waiting for spilled buckets, then moves the largest buckets and matches
+    /// of the trie into new spill files until at most half of the budget is used.
+    fn spill(&mut self) -> io::Result<()> {
+        for spill in self.spills.values_mut() {
+            self.used -= spill.flush()?;
+        }
+
+        if self.used <= self.max_memory / 2 {
+            return Ok(());
+        }
+
+        // buckets above the minimum level can't be spilled, such as the root before its first
+        // burst, so they are burst early
+        self.root.burst_above(self.min_level);
+
+        let min_level = self.min_level;
+        // matches can be spilled at any level, as their files are never sorted again
+        let spillable = |level: usize, list: &[Vec<u8>]| {
+            !list.is_empty() && (level >= min_level || list[0].len() == level)
+        };
+
+        let mut weights = Vec::new();
+        self.root.for_each_bucket(&mut |level, list| {
+            if spillable(level, list) {
+                weights.push(list.iter().map(|x| weight(x)).sum::<usize>());
+            }
+        });
+        weights.sort_unstable_by(|lhs, rhs| rhs.cmp(lhs));
+
+        // smallest of the largest buckets which together free enough memory
+        let mut excess = self.used - self.max_memory / 2;
+        let mut threshold = usize::MAX;
+
+        for x in weights {
+            threshold = x;
+
+            if x >= excess {
+                break;
+            }
+
+            excess -= x;
+        }
+
+        let mut buckets = Vec::new();
+        self.root.take_buckets(&mut |level, list| {
+            spillable(level, list) && list.iter().map(|x| weight(x)).sum::<usize>() >= threshold
+        }, &mut buckets);
+
+        for (level, list) in buckets {
+            let exact = list[0].len() == level;
+            let dir = &self.dir;
+
+            // the matches of a list node share their key with the prefix of its bucket
+            let spill = self.spills.entry(list[0][..level].to_vec()).or_insert_with(|| Spill {
+                path: dir.join(format!("{}.spill", next_id())),
+                pending: Vec::new(),
+                size: 0,
+                exact,
+            });
+
+            spill.exact &= exact;
+            spill.pending.extend(list);
+            self.used -= spill.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<C> Drop for ExternalSorter<C> {
+    fn drop(&mut self) {
+        // the directory can only be removed once the remaining spill files are
+        self.spills.clear();
+
+        if self.owns_dir {
+            let _ = fs::remove_dir(&self.dir);
+        }
+    }
+}
+
+/// Bucket of items which were moved out of memory.
+struct Spill {
+    path: PathBuf,
+    /// Items waiting to be appended to the file.
+    pending: Vec<Vec<u8>>,
+    /// Total weight of the items in the file.
+    size: usize,
+    /// Whether the items all have exactly the key of the spill, rather than sharing it as a
+    /// prefix, as when spilling matches.
+    exact: bool,
+}
+
+impl Spill {
+    /// Appends the pending items to the file, returning their weight.
+    fn flush(&mut self) -> io::Result<usize> {
+        if self.pending.is_empty() {
+            return Ok(0);
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        let mut writer = BufWriter::new(file);
+        let mut freed = 0;
+
+        for x in mem::take(&mut self.pending) {
+            writer.write_all(&(x.len() as u64).to_le_bytes())?;
+            writer.write_all(&x)?;
+            freed += weight(&x);
+        }
+
+        writer.flush()?;
+        self.size += freed;
+
+        Ok(freed)
+    }
+}
+
+impl Drop for Spill {
+    /// Removes the file if it was not sorted, such as when sorting an earlier spill failed.
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Reads the next length-prefixed item of a spill file.
+fn read_item<R: BufRead>(reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+    if reader.fill_buf()?.is_empty() {
+        return Ok(None);
+    }
+
+    let mut len = [0; 8];
+    reader.read_exact(&mut len)?;
+
+    let mut item = vec![0; u64::from_le_bytes(len) as usize];
+    reader.read_exact(&mut item)?;
+
+    Ok(Some(item))
+}
+
+/// Approximate memory held by an item.
+fn weight(item: &[u8]) -> usize {
+    item.len() + mem::size_of::<Vec<u8>>()
+}
+
+fn next_id() -> usize {
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
@@ -3,12 +3,31 @@
 extern crate quickcheck_macros;
 
 use std::borrow::Borrow;
+use std::ops::Range;
 
+pub use crate::burst_counter::BurstCounter;
+pub use crate::burst_map::BurstMap;
+pub use crate::external::ExternalSorter;
+pub use crate::burst_trie::BurstTrie;
+pub use crate::sorter::{BurstSorter, IntoSortedIter};
+pub use crate::spans::Span;
 pub use crate::trie::BurstConfig;
-use crate::trie::TrieNode;
+use crate::arena::ArenaTrie;
+use crate::merge::{Counts, Dedup, Groups, Lcp};
+use crate::trie::{ItemKeys, SliceKeys, SpanKeys, Trie};
 
+mod arena;
+mod blocks;
+mod merge;
+pub mod burst_counter;
+pub mod burst_map;
+pub mod burst_trie;
+mod external;
+mod sorter;
 mod trie;
 
+pub mod spans;
+
 #[cfg(feature = "_benchmarking")]
 pub mod benching;
 
@@ -20,6 +39,10 @@ pub const ASCII_CONFIG: BurstConfig = BurstConfig {
     initial_capacity: 256,
     classes: 127,
     hint_long: false,
+    max_memory: None,
+    block_buckets: false,
+    burst_bytes: None,
+    burst_schedule: None,
 };
 
 pub const LONG_ASCII_CONFIG: BurstConfig = BurstConfig {
@@ -37,6 +60,14 @@ pub const LONG_UTF8_CONFIG: BurstConfig = BurstConfig {
     ..UTF8_CONFIG
 };
 
+/// Config for the burst trie collections, which keep their buckets sorted as items are inserted
+/// and so favor smaller buckets.
+pub const COLLECTION_CONFIG: BurstConfig = BurstConfig {
+    burst_limit: 1024,
+    initial_capacity: 0,
+    ..UTF8_CONFIG
+};
+
 /// Sorts the provided data using a burstsort algorithm.
 ///
 /// # Arguments
@@ -47,7 +78,7 @@ pub const LONG_UTF8_CONFIG: BurstConfig = BurstConfig {
 /// * `T`: Type to be sorted. Must be able to be used as a slice of `I`.
 /// * `C`: Generic reference to a burstsort config.
 /// * `I`: Generic type that `T` is an array of. Must be able to be casted to [usize]. For
-/// strings, this would be [u8], for example.
+///   strings, this would be [u8], for example.
 ///
 /// # Panicking
 /// This function *might* panic if inputs contain radixes that are larger than the provided
@@ -73,7 +104,7 @@ pub fn burstsort<T, C, I>(data: &mut Vec<T>, config: C)
           C: Borrow<BurstConfig> + Clone + Send + Sync,
           I: Into<usize> + Clone + Ord + Send + Sync
 {
-    let mut root = TrieNode::root(config);
+    let mut root = Trie::new(config, ItemKeys);
 
     for x in data.drain(..) {
         root.insert(x);
@@ -88,11 +119,456 @@ pub fn par_burstsort<T, C, I>(data: &mut Vec<T>, config: C)
           C: Borrow<BurstConfig> + Clone + Send + Sync,
           I: Into<usize> + Clone + Ord + Send + Sync
 {
-    let mut root = TrieNode::root(config);
+    let mut root = Trie::new(config, ItemKeys);
 
     for x in data.drain(..) {
         root.insert(x);
     }
 
     root.par_merge(data);
-}
\ No newline at end of file
+}
+
+/// Sorts the provided data using a burst trie which allocates from arenas.
+///
+/// Nodes are kept in a single vector and buckets are chains of fixed-size blocks from a shared
+/// slab, so the trie makes far fewer allocations than [burstsort] and is freed all at once. This
+/// mostly matters under slower allocators.
+///
+/// See [burstsort] for details on the parameters and panicking behavior.
+///
+/// # Examples
+/// ```
+/// let mut strings = vec!["apple", "strawberry", "pear", "orange", "banana"];
+/// let sorted_strings = vec!["apple", "banana", "orange", "pear", "strawberry"];
+///
+/// burstsort::arena_burstsort(&mut strings, &burstsort::ASCII_CONFIG);
+///
+/// assert_eq!(sorted_strings, strings);
+/// ```
+pub fn arena_burstsort<T, C, I>(data: &mut Vec<T>, config: C)
+    where T: AsRef<[I]>,
+          C: Borrow<BurstConfig>,
+          I: Into<usize> + Clone + Ord
+{
+    let mut trie = ArenaTrie::new(config);
+
+    for x in data.drain(..) {
+        trie.insert(x);
+    }
+
+    trie.merge(data);
+}
+
+/// Computes the permutation which sorts the provided data, leaving the data itself untouched.
+///
+/// The trie stores indices into `data` and reads keys from the slice, so nothing is cloned or
+/// moved. Element `i` of the output is the index of the `i`th smallest item in `data`.
+///
+/// See [burstsort] for details on the parameters and panicking behavior.
+///
+/// # Examples
+/// ```
+/// let names = vec!["pear", "apple", "orange"];
+/// let prices = vec![3, 1, 2];
+///
+/// let order = burstsort::burstsort_indices(&names, &burstsort::ASCII_CONFIG);
+///
+/// assert_eq!(vec![1, 2, 0], order);
+/// assert_eq!(vec![1, 2, 3], order.iter().map(|&i| prices[i]).collect::<Vec<_>>());
+/// ```
+pub fn burstsort_indices<T, C, I>(data: &[T], config: C) -> Vec<usize>
+    where T: AsRef<[I]> + Sync,
+          C: Borrow<BurstConfig> + Clone + Send + Sync,
+          I: Into<usize> + Clone + Ord + Send + Sync
+{
+    let mut root = Trie::new(config, SliceKeys::new(data));
+
+    for i in 0..data.len() {
+        root.insert(i);
+    }
+
+    let mut indices = Vec::with_capacity(data.len());
+
+    root.merge(&mut indices);
+
+    indices
+}
+
+/// Parallel version of [burstsort_indices].
+#[cfg(feature = "parallelization")]
+pub fn par_burstsort_indices<T, C, I>(data: &[T], config: C) -> Vec<usize>
+    where T: AsRef<[I]> + Sync,
+          C: Borrow<BurstConfig> + Clone + Send + Sync,
+          I: Into<usize> + Clone + Ord + Send + Sync
+{
+    let mut root = Trie::new(config, SliceKeys::new(data));
+
+    for i in 0..data.len() {
+        root.insert(i);
+    }
+
+    let mut indices = Vec::with_capacity(data.len());
+
+    root.par_merge(&mut indices);
+
+    indices
+}
+
+/// Sorts references to the provided data, leaving the data itself untouched.
+///
+/// This avoids having to clone read-only data in order to sort it, and does not require `T` to
+/// be [Clone].
+///
+/// See [burstsort] for details on the parameters and panicking behavior.
+///
+/// # Examples
+/// ```
+/// let strings = vec![String::from("pear"), String::from("apple"), String::from("orange")];
+///
+/// let sorted = burstsort::burstsort_refs(&strings, &burstsort::ASCII_CONFIG);
+///
+/// assert_eq!(vec!["apple", "orange", "pear"], sorted);
+/// ```
+pub fn burstsort_refs<T, C, I>(data: &[T], config: C) -> Vec<&T>
+    where T: AsRef<[I]> + Sync,
+          C: Borrow<BurstConfig> + Clone + Send + Sync,
+          I: Into<usize> + Clone + Ord + Send + Sync
+{
+    let mut root = Trie::new(config, ItemKeys);
+
+    for x in data {
+        root.insert(x);
+    }
+
+    let mut refs = Vec::with_capacity(data.len());
+
+    root.merge(&mut refs);
+
+    refs
+}
+
+/// Parallel version of [burstsort_refs].
+#[cfg(feature = "parallelization")]
+pub fn par_burstsort_refs<T, C, I>(data: &[T], config: C) -> Vec<&T>
+    where T: AsRef<[I]> + Sync,
+          C: Borrow<BurstConfig> + Clone + Send + Sync,
+          I: Into<usize> + Clone + Ord + Send + Sync
+{
+    let mut root = Trie::new(config, ItemKeys);
+
+    for x in data {
+        root.insert(x);
+    }
+
+    let mut refs = Vec::with_capacity(data.len());
+
+    root.par_merge(&mut refs);
+
+    refs
+}
+
+/// Sorts spans of a single buffer by the contents of the buffer they cover.
+///
+/// This is useful for sorting the tokens of a large text without allocating an owned string for
+/// every token. Spans can be produced by the tokenizers in the [spans] module. Sorting borrowed
+/// tokens such as `Vec<&str>` directly with [burstsort] also works.
+///
+/// See [burstsort] for details on the parameters and panicking behavior.
+///
+/// # Panicking
+/// Panics if any span is out of bounds for the buffer.
+///
+/// # Examples
+/// ```
+/// use burstsort::spans::line_spans;
+///
+/// let text = "pear\napple\norange\n";
+/// let mut spans = line_spans(text);
+///
+/// burstsort::burstsort_spans(text.as_bytes(), &mut spans, &burstsort::ASCII_CONFIG);
+///
+/// let lines: Vec<_> = spans.iter().map(|s| s.get_str(text)).collect();
+/// assert_eq!(vec!["apple", "orange", "pear"], lines);
+/// ```
+pub fn burstsort_spans<C, I>(buffer: &[I], spans: &mut Vec<Span>, config: C)
+    where C: Borrow<BurstConfig> + Clone + Send + Sync,
+          I: Into<usize> + Clone + Ord + Send + Sync
+{
+    let mut root = Trie::new(config, SpanKeys::new(buffer));
+
+    for x in spans.drain(..) {
+        root.insert(x);
+    }
+
+    root.merge(spans);
+}
+
+/// Parallel version of [burstsort_spans].
+#[cfg(feature = "parallelization")]
+pub fn par_burstsort_spans<C, I>(buffer: &[I], spans: &mut Vec<Span>, config: C)
+    where C: Borrow<BurstConfig> + Clone + Send + Sync,
+          I: Into<usize> + Clone + Ord + Send + Sync
+{
+    let mut root = Trie::new(config, SpanKeys::new(buffer));
+
+    for x in spans.drain(..) {
+        root.insert(x);
+    }
+
+    root.par_merge(spans);
+}
+
+/// Sorts the provided data using a burstsort algorithm, keeping only one item for each key.
+///
+/// Duplicates are collapsed as the trie is merged, rather than in a separate pass. Items are
+/// considered duplicates if their keys are equal.
+///
+/// See [burstsort] for details on the parameters and panicking behavior.
+///
+/// # Examples
+/// ```
+/// let mut words = vec!["the", "cat", "and", "the", "hat"];
+///
+/// burstsort::burstsort_dedup(&mut words, &burstsort::ASCII_CONFIG);
+///
+/// assert_eq!(vec!["and", "cat", "hat", "the"], words);
+/// ```
+pub fn burstsort_dedup<T, C, I>(data: &mut Vec<T>, config: C)
+    where T: AsRef<[I]> + Send + Sync,
+          C: Borrow<BurstConfig> + Clone + Send + Sync,
+          I: Into<usize> + Clone + Ord + Send + Sync
+{
+    let mut root = Trie::new(config, ItemKeys);
+
+    for x in data.drain(..) {
+        root.insert(x);
+    }
+
+    root.merge_into(&mut Dedup::new(data, ItemKeys));
+}
+
+/// Parallel version of [burstsort_dedup].
+#[cfg(feature = "parallelization")]
+pub fn par_burstsort_dedup<T, C, I>(data: &mut Vec<T>, config: C)
+    where T: AsRef<[I]> + Send + Sync,
+          C: Borrow<BurstConfig> + Clone + Send + Sync,
+          I: Into<usize> + Clone + Ord + Send + Sync
+{
+    let mut root = Trie::new(config, ItemKeys);
+
+    for x in data.drain(..) {
+        root.insert(x);
+    }
+
+    root.par_merge_into(&mut Dedup::new(data, ItemKeys));
+}
+
+/// Sorts the provided data using a burstsort algorithm, returning one item for each key along
+/// with the number of times that key occurred.
+///
+/// Duplicates are counted as the trie is merged, rather than in a separate pass. Items are
+/// considered duplicates if their keys are equal.
+///
+/// See [burstsort] for details on the parameters and panicking behavior.
+///
+/// # Examples
+/// ```
+/// let words = vec!["the", "cat", "and", "the", "hat"];
+///
+/// let counts = burstsort::burstsort_counts(words, &burstsort::ASCII_CONFIG);
+///
+/// assert_eq!(vec![("and", 1), ("cat", 1), ("hat", 1), ("the", 2)], counts);
+/// ```
+pub fn burstsort_counts<T, C, I>(data: Vec<T>, config: C) -> Vec<(T, usize)>
+    where T: AsRef<[I]> + Send + Sync,
+          C: Borrow<BurstConfig> + Clone + Send + Sync,
+          I: Into<usize> + Clone + Ord + Send + Sync
+{
+    let mut root = Trie::new(config, ItemKeys);
+
+    for x in data {
+        root.insert(x);
+    }
+
+    let mut counts = Vec::new();
+
+    root.merge_into(&mut Counts::new(&mut counts, ItemKeys));
+
+    counts
+}
+
+/// Parallel version of [burstsort_counts].
+#[cfg(feature = "parallelization")]
+pub fn par_burstsort_counts<T, C, I>(data: Vec<T>, config: C) -> Vec<(T, usize)>
+    where T: AsRef<[I]> + Send + Sync,
+          C: Borrow<BurstConfig> + Clone + Send + Sync,
+          I: Into<usize> + Clone + Ord + Send + Sync
+{
+    let mut root = Trie::new(config, ItemKeys);
+
+    for x in data {
+        root.insert(x);
+    }
+
+    let mut counts = Vec::new();
+
+    root.par_merge_into(&mut Counts::new(&mut counts, ItemKeys));
+
+    counts
+}
+
+/// Sorts the provided data using a burstsort algorithm, returning the ranges of the sorted data
+/// which hold runs of equal keys.
+///
+/// The ranges are in order and cover all of the data. They are found as the trie is merged,
+/// rather than in a separate pass.
+///
+/// See [burstsort] for details on the parameters and panicking behavior.
+///
+/// # Examples
+/// ```
+/// let mut words = vec!["the", "cat", "and", "the", "hat"];
+///
+/// let groups = burstsort::burstsort_groups(&mut words, &burstsort::ASCII_CONFIG);
+///
+/// assert_eq!(vec!["and", "cat", "hat", "the", "the"], words);
+/// assert_eq!(vec![0..1, 1..2, 2..3, 3..5], groups);
+/// ```
+pub fn burstsort_groups<T, C, I>(data: &mut Vec<T>, config: C) -> Vec<Range<usize>>
+    where T: AsRef<[I]> + Send + Sync,
+          C: Borrow<BurstConfig> + Clone + Send + Sync,
+          I: Into<usize> + Clone + Ord + Send + Sync
+{
+    let mut root = Trie::new(config, ItemKeys);
+
+    for x in data.drain(..) {
+        root.insert(x);
+    }
+
+    let mut groups = Vec::new();
+
+    root.merge_into(&mut Groups::new(data, &mut groups, ItemKeys));
+
+    groups
+}
+
+/// Parallel version of [burstsort_groups].
+#[cfg(feature = "parallelization")]
+pub fn par_burstsort_groups<T, C, I>(data: &mut Vec<T>, config: C) -> Vec<Range<usize>>
+    where T: AsRef<[I]> + Send + Sync,
+          C: Borrow<BurstConfig> + Clone + Send + Sync,
+          I: Into<usize> + Clone + Ord + Send + Sync
+{
+    let mut root = Trie::new(config, ItemKeys);
+
+    for x in data.drain(..) {
+        root.insert(x);
+    }
+
+    let mut groups = Vec::new();
+
+    root.par_merge_into(&mut Groups::new(data, &mut groups, ItemKeys));
+
+    groups
+}
+
+/// Sorts the provided data using a burstsort algorithm, returning its longest-common-prefix
+/// array.
+///
+/// Element `i` of the output is the length of the common prefix between the keys of the `i`th
+/// and `i - 1`th sorted items, and the first element is 0. The depth of the trie gives the common
+/// prefix between buckets, so only adjacent items within a bucket are compared.
+///
+/// See [burstsort] for details on the parameters and panicking behavior.
+///
+/// # Examples
+/// ```
+/// let mut words = vec!["banana", "apple", "band", "ban"];
+///
+/// let lcp = burstsort::burstsort_with_lcp(&mut words, &burstsort::ASCII_CONFIG);
+///
+/// assert_eq!(vec!["apple", "ban", "banana", "band"], words);
+/// assert_eq!(vec![0, 0, 3, 3], lcp);
+/// ```
+pub fn burstsort_with_lcp<T, C, I>(data: &mut Vec<T>, config: C) -> Vec<usize>
+    where T: AsRef<[I]> + Send + Sync,
+          C: Borrow<BurstConfig> + Clone + Send + Sync,
+          I: Into<usize> + Clone + Ord + Send + Sync
+{
+    let mut root = Trie::new(config, ItemKeys);
+
+    for x in data.drain(..) {
+        root.insert(x);
+    }
+
+    let mut lcps = Vec::with_capacity(data.capacity());
+
+    root.merge_into(&mut Lcp::new(data, &mut lcps, ItemKeys));
+
+    lcps
+}
+
+/// Parallel version of [burstsort_with_lcp].
+#[cfg(feature = "parallelization")]
+pub fn par_burstsort_with_lcp<T, C, I>(data: &mut Vec<T>, config: C) -> Vec<usize>
+    where T: AsRef<[I]> + Send + Sync,
+          C: Borrow<BurstConfig> + Clone + Send + Sync,
+          I: Into<usize> + Clone + Ord + Send + Sync
+{
+    let mut root = Trie::new(config, ItemKeys);
+
+    for x in data.drain(..) {
+        root.insert(x);
+    }
+
+    let mut lcps = Vec::with_capacity(data.capacity());
+
+    root.par_merge_into(&mut Lcp::new(data, &mut lcps, ItemKeys));
+
+    lcps
+}
+
+/// Sorts the provided data using a burstsort algorithm, keeping only the first `k` items.
+///
+/// The trie is walked in order and only the buckets holding the first `k` items are sorted, so
+/// this is much cheaper than a full sort when `k` is small.
+///
+/// See [burstsort] for details on the parameters and panicking behavior.
+///
+/// # Examples
+/// ```
+/// let mut strings = vec!["apple", "strawberry", "pear", "orange", "banana"];
+///
+/// burstsort::burstsort_top_k(&mut strings, 2, &burstsort::ASCII_CONFIG);
+///
+/// assert_eq!(vec!["apple", "banana"], strings);
+/// ```
+pub fn burstsort_top_k<T, C, I>(data: &mut Vec<T>, k: usize, config: C)
+    where T: AsRef<[I]> + Send + Sync,
+          C: Borrow<BurstConfig> + Clone + Send + Sync,
+          I: Into<usize> + Clone + Ord + Send + Sync
+{
+    let mut root = Trie::new(config, ItemKeys);
+
+    for x in data.drain(..) {
+        root.insert(x);
+    }
+
+    root.merge_top_k(data, k);
+}
+
+/// Parallel version of [burstsort_top_k].
+#[cfg(feature = "parallelization")]
+pub fn par_burstsort_top_k<T, C, I>(data: &mut Vec<T>, k: usize, config: C)
+    where T: AsRef<[I]> + Send + Sync,
+          C: Borrow<BurstConfig> + Clone + Send + Sync,
+          I: Into<usize> + Clone + Ord + Send + Sync
+{
+    let mut root = Trie::new(config, ItemKeys);
+
+    for x in data.drain(..) {
+        root.insert(x);
+    }
+
+    root.par_merge_top_k(data, k);
+}
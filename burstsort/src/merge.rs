@@ -0,0 +1,220 @@
+//! Sinks which receive the sorted contents of a trie as it is merged.
+
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use crate::trie::KeySource;
+
+/// Receives the sorted contents of a trie, one group of items at a time.
+///
+/// Each callback must drain the provided vector. `lcp` is the length of the common prefix
+/// between the first item of the group and the last item received before it.
+pub trait MergeSink<T> {
+    /// Receives items whose keys all end at `level`, meaning they all have equal keys.
+    fn matches(&mut self, items: &mut Vec<T>, level: usize, lcp: usize);
+
+    /// Receives a sorted bucket of items which share their first `level` key elements.
+    fn bucket(&mut self, items: &mut Vec<T>, level: usize, lcp: usize);
+}
+
+/// Appends every item to the target.
+pub struct Append<'a, T> {
+    target: &'a mut Vec<T>,
+}
+
+impl<'a, T> Append<'a, T> {
+    pub fn new(target: &'a mut Vec<T>) -> Self {
+        Self { target }
+    }
+}
+
+impl<'a, T> MergeSink<T> for Append<'a, T> {
+    fn matches(&mut self, items: &mut Vec<T>, _level: usize, _lcp: usize) {
+        self.target.append(items);
+    }
+
+    fn bucket(&mut self, items: &mut Vec<T>, _level: usize, _lcp: usize) {
+        self.target.append(items);
+    }
+}
+
+/// Appends only the first item of each run of equal keys to the target.
+pub struct Dedup<'a, T, I, K> {
+    target: &'a mut Vec<T>,
+    keys: K,
+    _phantom: PhantomData<I>,
+}
+
+impl<'a, T, I, K> Dedup<'a, T, I, K> {
+    pub fn new(target: &'a mut Vec<T>, keys: K) -> Self {
+        Self {
+            target,
+            keys,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, T, I, K> MergeSink<T> for Dedup<'a, T, I, K>
+    where I: Ord,
+          K: KeySource<T, I>
+{
+    fn matches(&mut self, items: &mut Vec<T>, _level: usize, _lcp: usize) {
+        self.target.extend(items.drain(..).next());
+    }
+
+    fn bucket(&mut self, items: &mut Vec<T>, level: usize, _lcp: usize) {
+        let start = self.target.len();
+
+        for x in items.drain(..) {
+            if self.target.len() > start {
+                let last = &self.target[self.target.len() - 1];
+
+                if self.keys.key(last)[level..] == self.keys.key(&x)[level..] {
+                    continue;
+                }
+            }
+
+            self.target.push(x);
+        }
+    }
+}
+
+/// Appends the first item of each run of equal keys to the target, along with the run length.
+pub struct Counts<'a, T, I, K> {
+    target: &'a mut Vec<(T, usize)>,
+    keys: K,
+    _phantom: PhantomData<I>,
+}
+
+impl<'a, T, I, K> Counts<'a, T, I, K> {
+    pub fn new(target: &'a mut Vec<(T, usize)>, keys: K) -> Self {
+        Self {
+            target,
+            keys,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, T, I, K> MergeSink<T> for Counts<'a, T, I, K>
+    where I: Ord,
+          K: KeySource<T, I>
+{
+    fn matches(&mut self, items: &mut Vec<T>, _level: usize, _lcp: usize) {
+        let count = items.len();
+
+        self.target.extend(items.drain(..).next().map(|x| (x, count)));
+    }
+
+    fn bucket(&mut self, items: &mut Vec<T>, level: usize, _lcp: usize) {
+        let start = self.target.len();
+
+        for x in items.drain(..) {
+            if self.target.len() > start {
+                let last = self.target.last_mut().unwrap();
+
+                if self.keys.key(&last.0)[level..] == self.keys.key(&x)[level..] {
+                    last.1 += 1;
+                    continue;
+                }
+            }
+
+            self.target.push((x, 1));
+        }
+    }
+}
+
+/// Appends every item to the target, recording the range of each run of equal keys.
+pub struct Groups<'a, T, I, K> {
+    target: &'a mut Vec<T>,
+    groups: &'a mut Vec<Range<usize>>,
+    keys: K,
+    _phantom: PhantomData<I>,
+}
+
+impl<'a, T, I, K> Groups<'a, T, I, K> {
+    pub fn new(target: &'a mut Vec<T>, groups: &'a mut Vec<Range<usize>>, keys: K) -> Self {
+        Self {
+            target,
+            groups,
+            keys,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, T, I, K> MergeSink<T> for Groups<'a, T, I, K>
+    where I: Ord,
+          K: KeySource<T, I>
+{
+    fn matches(&mut self, items: &mut Vec<T>, _level: usize, _lcp: usize) {
+        let start = self.target.len();
+
+        self.groups.push(start..start + items.len());
+        self.target.append(items);
+    }
+
+    fn bucket(&mut self, items: &mut Vec<T>, level: usize, _lcp: usize) {
+        let start = self.target.len();
+        let mut run = start;
+
+        for (i, pair) in items.windows(2).enumerate() {
+            if self.keys.key(&pair[0])[level..] != self.keys.key(&pair[1])[level..] {
+                self.groups.push(run..start + i + 1);
+                run = start + i + 1;
+            }
+        }
+
+        self.groups.push(run..start + items.len());
+        self.target.append(items);
+    }
+}
+
+/// Appends every item to the target, recording the length of the common prefix between each
+/// item and the one before it.
+pub struct Lcp<'a, T, I, K> {
+    target: &'a mut Vec<T>,
+    lcps: &'a mut Vec<usize>,
+    keys: K,
+    _phantom: PhantomData<I>,
+}
+
+impl<'a, T, I, K> Lcp<'a, T, I, K> {
+    pub fn new(target: &'a mut Vec<T>, lcps: &'a mut Vec<usize>, keys: K) -> Self {
+        Self {
+            target,
+            lcps,
+            keys,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, T, I, K> MergeSink<T> for Lcp<'a, T, I, K>
+    where I: Ord,
+          K: KeySource<T, I>
+{
+    fn matches(&mut self, items: &mut Vec<T>, level: usize, lcp: usize) {
+        // matching keys are identical, and end at this level
+        self.lcps.push(lcp);
+        self.lcps.extend(std::iter::repeat_n(level, items.len() - 1));
+        self.target.append(items);
+    }
+
+    fn bucket(&mut self, items: &mut Vec<T>, level: usize, lcp: usize) {
+        // bucket items share the prefix leading to the bucket, so only the rest is compared
+        self.lcps.push(lcp);
+
+        for pair in items.windows(2) {
+            let lhs = &self.keys.key(&pair[0])[level..];
+            let rhs = &self.keys.key(&pair[1])[level..];
+
+            let common = lhs.iter().zip(rhs).take_while(|(l, r)| l == r).count();
+
+            self.lcps.push(level + common);
+        }
+
+        self.target.append(items);
+    }
+}
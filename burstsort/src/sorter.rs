@@ -0,0 +1,152 @@
+use std::borrow::Borrow;
+
+use crate::trie::{self, BurstConfig, ItemKeys, Trie};
+
+/// Incremental burstsort, for when the items to sort are not all available up front.
+///
+/// # Type Parameters
+/// See [burstsort](crate::burstsort) for details on the type parameters.
+///
+/// # Examples
+/// ```
+/// use burstsort::BurstSorter;
+///
+/// let mut sorter = BurstSorter::new(&burstsort::ASCII_CONFIG);
+///
+/// sorter.insert("pear");
+/// sorter.extend(vec!["apple", "orange"]);
+///
+/// let sorted: Vec<_> = sorter.into_sorted_iter().collect();
+///
+/// assert_eq!(vec!["apple", "orange", "pear"], sorted);
+/// ```
+///
+/// A sorter can also be kept around to sort many batches with [BurstSorter::sort], reusing the
+/// node tables and buckets of the trie between batches.
+pub struct BurstSorter<T, C = &'static BurstConfig, I = u8> {
+    root: Trie<C, T, I>,
+    /// Most memory the trie may keep between batches, if limited.
+    max_retained: Option<usize>,
+}
+
+impl<T, C, I> BurstSorter<T, C, I>
+    where T: AsRef<[I]> + Send + Sync,
+          C: Borrow<BurstConfig> + Clone + Send + Sync,
+          I: Into<usize> + Clone + Ord + Send + Sync
+{
+    pub fn new(config: C) -> Self {
+        Self {
+            root: Trie::new(config, ItemKeys),
+            max_retained: None,
+        }
+    }
+
+    /// Limits the memory the trie keeps between calls to [BurstSorter::sort], in bytes as
+    /// counted by [BurstSorter::memory_used]. A trie which grew past the limit is freed after
+    /// sorting, rather than kept.
+    pub fn set_max_retained(&mut self, max_retained: Option<usize>) {
+        self.max_retained = max_retained;
+    }
+
+    /// Sorts the data, along with any items inserted beforehand.
+    ///
+    /// Unlike [burstsort](crate::burstsort), the trie is emptied rather than dropped afterwards,
+    /// so its node tables and buckets are reused by the next call. This saves a lot of
+    /// allocator work when sorting many small batches.
+    ///
+    /// # Examples
+    /// ```
+    /// use burstsort::BurstSorter;
+    ///
+    /// let mut sorter = BurstSorter::new(&burstsort::ASCII_CONFIG);
+    ///
+    /// for batch in &[vec!["pear", "apple"], vec!["orange", "banana", "fig"]] {
+    ///     let mut batch = batch.clone();
+    ///     sorter.sort(&mut batch);
+    ///
+    ///     assert!(batch.windows(2).all(|x| x[0] <= x[1]));
+    /// }
+    /// ```
+    pub fn sort(&mut self, data: &mut Vec<T>) {
+        for x in data.drain(..) {
+            self.root.insert(x);
+        }
+
+        self.root.merge(data);
+
+        if self.max_retained.is_some_and(|max| self.root.memory_used() > max) {
+            self.root.reset();
+        }
+    }
+
+    pub fn insert(&mut self, item: T) {
+        self.root.insert(item);
+    }
+
+    /// Moves every item of `other` into this sorter, leaving it empty.
+    ///
+    /// This is meant for shards which were collected separately: the tries are merged node by
+    /// node, without re-inserting each item. Both sorters should use the same config.
+    ///
+    /// # Examples
+    /// ```
+    /// use burstsort::BurstSorter;
+    ///
+    /// let mut a = BurstSorter::new(&burstsort::ASCII_CONFIG);
+    /// let mut b = BurstSorter::new(&burstsort::ASCII_CONFIG);
+    ///
+    /// a.extend(vec!["pear", "apple"]);
+    /// b.extend(vec!["orange", "fig"]);
+    /// a.append(&mut b);
+    ///
+    /// let sorted: Vec<_> = a.into_sorted_iter().collect();
+    ///
+    /// assert_eq!(vec!["apple", "fig", "orange", "pear"], sorted);
+    /// ```
+    pub fn append(&mut self, other: &mut Self) {
+        self.root.append(&mut other.root, false);
+    }
+
+    /// Approximate bytes allocated by the sorter, as counted for [BurstConfig::max_memory].
+    pub fn memory_used(&self) -> usize {
+        self.root.memory_used()
+    }
+
+    /// Converts the sorter into an iterator over its items in order.
+    ///
+    /// Each bucket of the trie is only sorted once the iterator reaches it, so the first items
+    /// are available early and no second buffer is needed for the output.
+    pub fn into_sorted_iter(self) -> IntoSortedIter<T, C, I> {
+        IntoSortedIter {
+            inner: self.root.into_sorted_iter(),
+        }
+    }
+}
+
+impl<T, C, I> Extend<T> for BurstSorter<T, C, I>
+    where T: AsRef<[I]> + Send + Sync,
+          C: Borrow<BurstConfig> + Clone + Send + Sync,
+          I: Into<usize> + Clone + Ord + Send + Sync
+{
+    fn extend<It: IntoIterator<Item=T>>(&mut self, iter: It) {
+        for x in iter {
+            self.insert(x);
+        }
+    }
+}
+
+/// Iterator over the items of a [BurstSorter] in order.
+pub struct IntoSortedIter<T, C = &'static BurstConfig, I = u8> {
+    inner: trie::IntoSortedIter<C, T, I>,
+}
+
+impl<T, C, I> Iterator for IntoSortedIter<T, C, I>
+    where T: AsRef<[I]>,
+          I: Ord
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.inner.next()
+    }
+}
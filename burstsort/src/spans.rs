@@ -0,0 +1,68 @@
+//! Spans of a single text buffer, for sorting tokens without allocating a string per token.
+
+use std::ops::Range;
+
+#[cfg(feature = "tokenize")]
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A token within a buffer, stored as an offset and a length.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub offset: usize,
+    pub len: usize,
+}
+
+impl Span {
+    pub fn new(offset: usize, len: usize) -> Self {
+        Self { offset, len }
+    }
+
+    /// Range of the buffer covered by this span.
+    pub fn range(&self) -> Range<usize> {
+        self.offset..self.offset + self.len
+    }
+
+    /// Returns the part of the buffer covered by this span.
+    ///
+    /// # Panicking
+    /// Panics if the span is out of bounds for the buffer.
+    pub fn get<'a, I>(&self, buffer: &'a [I]) -> &'a [I] {
+        &buffer[self.range()]
+    }
+
+    /// Returns the part of the text covered by this span.
+    ///
+    /// # Panicking
+    /// Panics if the span is out of bounds for the text or does not fall on char boundaries.
+    pub fn get_str<'a>(&self, text: &'a str) -> &'a str {
+        &text[self.range()]
+    }
+}
+
+/// Splits the text into lines, without their line terminators.
+///
+/// Lines are split the same way as [str::lines], so `\n` and `\r\n` are both terminators.
+pub fn line_spans(text: &str) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut offset = 0;
+
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.strip_suffix('\n').unwrap_or(line);
+        let trimmed = trimmed.strip_suffix('\r').unwrap_or(trimmed);
+
+        spans.push(Span::new(offset, trimmed.len()));
+
+        offset += line.len();
+    }
+
+    spans
+}
+
+/// Splits the text into unicode words, using the same rules as
+/// [UnicodeSegmentation::unicode_words].
+#[cfg(feature = "tokenize")]
+pub fn word_spans(text: &str) -> Vec<Span> {
+    text.unicode_word_indices()
+        .map(|(offset, word)| Span::new(offset, word.len()))
+        .collect()
+}
@@ -1,37 +1,1730 @@
 use super::*;
 
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::{Bound, Range};
+
+/// Config with small limits, so that even short inputs burst.
+fn small_config(hint_long: bool) -> BurstConfig {
+    BurstConfig {
+        burst_limit: 8,
+        initial_capacity: 4,
+        hint_long,
+        ..UTF8_CONFIG
+    }
+}
+
+#[quickcheck]
+fn check_sort_string(mut data: Vec<String>) {
+    let config = small_config(false);
+
+    let mut expected = data.clone();
+
+    burstsort(&mut data, &config);
+
+    expected.sort();
+
+    assert_eq!(expected, data);
+}
+
+#[quickcheck]
+fn check_sort_string_long(mut data: Vec<String>) {
+    let config = small_config(true);
+
+    let mut expected = data.clone();
+
+    burstsort(&mut data, &config);
+
+    expected.sort();
+
+    assert_eq!(expected, data);
+}
+
+#[quickcheck]
+fn check_arena_sort(mut data: Vec<String>) {
+    let config = small_config(false);
+
+    let mut expected = data.clone();
+
+    arena_burstsort(&mut data, &config);
+
+    expected.sort();
+
+    assert_eq!(expected, data);
+}
+
+#[quickcheck]
+fn check_sort_indices(data: Vec<String>) {
+    let config = small_config(false);
+
+    let mut expected = data.clone();
+    expected.sort();
+
+    let indices = burstsort_indices(&data, &config);
+    let actual: Vec<_> = indices.iter().map(|&i| data[i].clone()).collect();
+
+    let mut seen = indices.clone();
+    seen.sort_unstable();
+
+    assert_eq!((0..data.len()).collect::<Vec<_>>(), seen);
+    assert_eq!(expected, actual);
+}
+
+#[cfg(feature = "parallelization")]
+#[quickcheck]
+fn check_par_sort_indices(data: Vec<String>) {
+    let config = small_config(true);
+
+    let mut expected = data.clone();
+    expected.sort();
+
+    let actual: Vec<_> = par_burstsort_indices(&data, &config)
+        .into_iter()
+        .map(|i| data[i].clone())
+        .collect();
+
+    assert_eq!(expected, actual);
+}
+
+#[quickcheck]
+fn check_sort_refs(data: Vec<String>) {
+    let config = small_config(false);
+
+    let mut expected = data.clone();
+    expected.sort();
+
+    let actual: Vec<&String> = burstsort_refs(&data, &config);
+
+    assert_eq!(expected.iter().collect::<Vec<_>>(), actual);
+}
+
+#[cfg(feature = "parallelization")]
+#[quickcheck]
+fn check_par_sort_refs(data: Vec<String>) {
+    let config = small_config(true);
+
+    let mut expected = data.clone();
+    expected.sort();
+
+    let actual: Vec<&String> = par_burstsort_refs(&data, &config);
+
+    assert_eq!(expected.iter().collect::<Vec<_>>(), actual);
+}
+
+#[quickcheck]
+fn check_sort_spans(data: Vec<String>) {
+    let config = small_config(false);
+
+    let text = data.concat();
+
+    let mut offset = 0;
+    let mut spans: Vec<_> = data.iter()
+        .map(|s| {
+            offset += s.len();
+            Span::new(offset - s.len(), s.len())
+        })
+        .collect();
+
+    let mut expected = data.clone();
+    expected.sort();
+
+    burstsort_spans(text.as_bytes(), &mut spans, &config);
+
+    let actual: Vec<_> = spans.iter().map(|s| s.get_str(&text)).collect();
+
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn check_line_spans() {
+    let text = "pear\r\napple\n\norange";
+
+    let lines: Vec<_> = spans::line_spans(text).iter().map(|s| s.get_str(text)).collect();
+
+    assert_eq!(text.lines().collect::<Vec<_>>(), lines);
+}
+
+#[cfg(feature = "tokenize")]
+#[test]
+fn check_word_spans() {
+    use unicode_segmentation::UnicodeSegmentation;
+
+    let text = "The quick (\"brown\") fox can't jump 32.3 feet, right?";
+
+    let words: Vec<_> = spans::word_spans(text).iter().map(|s| s.get_str(text)).collect();
+
+    assert_eq!(text.unicode_words().collect::<Vec<_>>(), words);
+}
+
+#[quickcheck]
+fn check_sort_dedup(mut data: Vec<String>) {
+    let config = small_config(false);
+
+    // make duplicates likely
+    data.extend(data.clone());
+
+    let mut expected = data.clone();
+    expected.sort();
+    expected.dedup();
+
+    burstsort_dedup(&mut data, &config);
+
+    assert_eq!(expected, data);
+}
+
+#[quickcheck]
+fn check_sort_counts(mut data: Vec<String>) {
+    let config = small_config(true);
+
+    data.extend(data.clone());
+
+    let mut expected: Vec<(String, usize)> = Vec::new();
+    let mut sorted = data.clone();
+    sorted.sort();
+
+    for x in sorted {
+        match expected.last_mut() {
+            Some((last, count)) if *last == x => *count += 1,
+            _ => expected.push((x, 1)),
+        }
+    }
+
+    assert_eq!(expected, burstsort_counts(data, &config));
+}
+
+#[cfg(feature = "parallelization")]
+#[quickcheck]
+fn check_par_sort_dedup(mut data: Vec<String>) {
+    let config = small_config(false);
+
+    data.extend(data.clone());
+
+    let mut expected = data.clone();
+    expected.sort();
+    expected.dedup();
+
+    let counts = par_burstsort_counts(data.clone(), &config);
+
+    par_burstsort_dedup(&mut data, &config);
+
+    assert_eq!(expected, data);
+    assert_eq!(expected, counts.into_iter().map(|(x, _)| x).collect::<Vec<_>>());
+}
+
+#[cfg(feature = "parallelization")]
+#[quickcheck]
+fn check_par_sort_counts(mut data: Vec<String>) {
+    let config = small_config(false);
+
+    data.extend(data.clone());
+
+    assert_eq!(burstsort_counts(data.clone(), &config), par_burstsort_counts(data, &config));
+}
+
+fn expected_groups(sorted: &[String]) -> Vec<Range<usize>> {
+    let mut groups: Vec<Range<usize>> = Vec::new();
+
+    for (i, x) in sorted.iter().enumerate() {
+        match groups.last_mut() {
+            Some(group) if sorted[group.start] == *x => group.end += 1,
+            _ => groups.push(i..i + 1),
+        }
+    }
+
+    groups
+}
+
+#[quickcheck]
+fn check_sort_groups(mut data: Vec<String>) {
+    let config = small_config(false);
+
+    data.extend(data.clone());
+
+    let mut expected = data.clone();
+    expected.sort();
+
+    let groups = burstsort_groups(&mut data, &config);
+
+    assert_eq!(expected, data);
+    assert_eq!(expected_groups(&expected), groups);
+}
+
+#[cfg(feature = "parallelization")]
+#[quickcheck]
+fn check_par_sort_groups(mut data: Vec<String>) {
+    let config = small_config(true);
+
+    data.extend(data.clone());
+
+    let mut expected = data.clone();
+    expected.sort();
+
+    let groups = par_burstsort_groups(&mut data, &config);
+
+    assert_eq!(expected, data);
+    assert_eq!(expected_groups(&expected), groups);
+}
+
+fn expected_lcps(sorted: &[String]) -> Vec<usize> {
+    let mut lcps = Vec::new();
+
+    for (i, x) in sorted.iter().enumerate() {
+        let lcp = match i {
+            0 => 0,
+            _ => sorted[i - 1].bytes().zip(x.bytes()).take_while(|(l, r)| l == r).count(),
+        };
+
+        lcps.push(lcp);
+    }
+
+    lcps
+}
+
+#[quickcheck]
+fn check_sort_with_lcp(mut data: Vec<String>, prefixes: Vec<String>) {
+    let config = small_config(false);
+
+    // shared prefixes make for interesting common prefixes
+    let prefixed: Vec<_> = prefixes.iter().take(3)
+        .flat_map(|p| data.iter().map(move |s| format!("{}{}", p, s)))
+        .collect();
+    data.extend(prefixed);
+
+    let mut expected = data.clone();
+    expected.sort();
+
+    let lcps = burstsort_with_lcp(&mut data, &config);
+
+    assert_eq!(expected, data);
+    assert_eq!(expected_lcps(&expected), lcps);
+}
+
+#[cfg(feature = "parallelization")]
+#[quickcheck]
+fn check_par_sort_with_lcp(mut data: Vec<String>, prefixes: Vec<String>) {
+    let config = small_config(true);
+
+    let prefixed: Vec<_> = prefixes.iter().take(3)
+        .flat_map(|p| data.iter().map(move |s| format!("{}{}", p, s)))
+        .collect();
+    data.extend(prefixed);
+
+    let mut expected = data.clone();
+    expected.sort();
+
+    let lcps = par_burstsort_with_lcp(&mut data, &config);
+
+    assert_eq!(expected, data);
+    assert_eq!(expected_lcps(&expected), lcps);
+}
+
+#[quickcheck]
+fn check_sort_top_k(mut data: Vec<String>, k: usize) {
+    let config = small_config(false);
+
+    let k = k % (data.len() + 2);
+
+    let mut expected = data.clone();
+    expected.sort();
+    expected.truncate(k);
+
+    burstsort_top_k(&mut data, k, &config);
+
+    assert_eq!(expected, data);
+}
+
+#[cfg(feature = "parallelization")]
+#[quickcheck]
+fn check_par_sort_top_k(mut data: Vec<String>, k: usize) {
+    let config = small_config(true);
+
+    let k = k % (data.len() + 2);
+
+    let mut expected = data.clone();
+    expected.sort();
+    expected.truncate(k);
+
+    par_burstsort_top_k(&mut data, k, &config);
+
+    assert_eq!(expected, data);
+}
+
+#[quickcheck]
+fn check_sorted_iter(data: Vec<String>) {
+    let config = small_config(false);
+
+    let mut expected = data.clone();
+    expected.sort();
+
+    let mut sorter = BurstSorter::new(&config);
+    sorter.extend(data);
+
+    assert_eq!(expected, sorter.into_sorted_iter().collect::<Vec<_>>());
+}
+
+#[quickcheck]
+fn check_burst_trie(data: Vec<String>, more: Vec<String>) {
+    let config = small_config(false);
+
+    let mut trie = BurstTrie::with_config(&config);
+    trie.extend(data.clone());
+
+    let mut expected = data;
+    expected.sort();
+
+    assert_eq!(expected.len(), trie.len());
+    assert_eq!(expected.iter().collect::<Vec<_>>(), trie.iter().collect::<Vec<_>>());
+
+    // snapshots stay valid as more items are added
+    trie.extend(more.clone());
+    expected.extend(more);
+    expected.sort();
+
+    assert_eq!(expected.len(), trie.iter().len());
+    assert_eq!(expected, trie.into_sorted_vec());
+}
+
+#[quickcheck]
+fn check_burst_map(ops: Vec<(String, Option<u8>)>, lower: String, upper: String) {
+    let config = small_config(false);
+
+    let mut map = BurstMap::with_config(&config);
+    let mut expected = BTreeMap::new();
+
+    // insert a value, or remove the key if there is none
+    for (key, value) in ops {
+        match value {
+            Some(value) => assert_eq!(expected.insert(key.clone(), value), map.insert(key, value)),
+            None => assert_eq!(expected.remove(&key), map.remove(&key)),
+        }
+
+        assert_eq!(expected.len(), map.len());
+    }
+
+    for (key, value) in &expected {
+        assert_eq!(Some(value), map.get(key));
+        map.entry(key.clone()).and_modify(|v| *v = v.wrapping_add(1)).or_insert(0);
+    }
+
+    for value in expected.values_mut() {
+        *value = value.wrapping_add(1);
+    }
+
+    assert_eq!(expected.iter().collect::<Vec<_>>(), map.iter().collect::<Vec<_>>());
+
+    if lower <= upper {
+        assert_eq!(
+            expected.range(lower.clone()..upper.clone()).collect::<Vec<_>>(),
+            map.range(lower.clone()..upper.clone()).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            expected.range(lower.clone()..=upper.clone()).collect::<Vec<_>>(),
+            map.range(lower.clone()..=upper.clone()).collect::<Vec<_>>()
+        );
+    }
+
+    assert_eq!(expected.range(lower.clone()..).collect::<Vec<_>>(),
+               map.range(lower..).collect::<Vec<_>>());
+    assert_eq!(expected.range(..upper.clone()).collect::<Vec<_>>(),
+               map.range(..upper).collect::<Vec<_>>());
+}
+
+/// Every string over a small alphabet up to the given length, so that keys share many prefixes.
+fn dense_keys(alphabet: &str, length: usize) -> Vec<String> {
+    let mut keys = vec![String::new()];
+
+    for i in 0..length {
+        let mut longer = Vec::new();
+
+        for key in keys.iter().filter(|k| k.len() == i) {
+            longer.extend(alphabet.chars().map(|c| format!("{}{}", key, c)));
+        }
+
+        keys.extend(longer);
+    }
+
+    keys
+}
+
+#[test]
+fn check_burst_map_range_dense() {
+    let config = BurstConfig {
+        burst_limit: 2,
+        initial_capacity: 0,
+        ..small_config(false)
+    };
+
+    let keys = dense_keys("abc", 4);
+
+    let map: BurstMap<_, _, _> = {
+        let mut map = BurstMap::with_config(&config);
+        map.extend(keys.iter().map(|k| (k.clone(), k.len())));
+        map
+    };
+    let expected: BTreeMap<_, _> = keys.iter().map(|k| (k.clone(), k.len())).collect();
+
+    let bounds = dense_keys("abd", 3);
+
+    for lower in &bounds {
+        for upper in bounds.iter().filter(|u| lower <= *u) {
+            assert_eq!(
+                expected.range(lower.clone()..upper.clone()).collect::<Vec<_>>(),
+                map.range(lower.clone()..upper.clone()).collect::<Vec<_>>()
+            );
+            assert_eq!(
+                expected.range(lower.clone()..=upper.clone()).collect::<Vec<_>>(),
+                map.range(lower.clone()..=upper.clone()).collect::<Vec<_>>()
+            );
+        }
+    }
+}
+
+#[quickcheck]
+fn check_burst_trie_prefix(data: Vec<String>, prefix: String) {
+    let config = BurstConfig {
+        burst_limit: 2,
+        initial_capacity: 0,
+        ..small_config(false)
+    };
+
+    // dense keys share plenty of prefixes with each other and with prefixes of the input
+    let prefix: String = prefix.chars().take(2).collect();
+    let mut data: Vec<_> = data.into_iter()
+        .map(|s| s.chars().take(3).collect::<String>())
+        .collect();
+    data.extend(dense_keys("ab", 3));
+    data.extend(dense_keys("a", 3));
+
+    let mut trie = BurstTrie::with_config(&config);
+    trie.extend(data.clone());
+
+    for prefix in [prefix.as_str(), "", "a", "ab", "aba", "abab"] {
+        let mut expected: Vec<_> = data.iter().filter(|s| s.starts_with(prefix)).collect();
+        expected.sort();
+
+        assert_eq!(expected, trie.iter_prefix(prefix).collect::<Vec<_>>());
+        assert_eq!(expected.len(), trie.count_prefix(prefix));
+
+        let mut counts: Vec<(&String, usize)> = Vec::new();
+        for x in expected {
+            match counts.last_mut() {
+                Some((last, count)) if *last == x => *count += 1,
+                _ => counts.push((x, 1)),
+            }
+        }
+        counts.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+        counts.truncate(3);
+
+        assert_eq!(counts, trie.most_common_with_prefix(prefix, 3));
+    }
+}
+
+#[quickcheck]
+fn check_burst_trie_range(data: Vec<String>, lower: String, upper: String) {
+    let config = BurstConfig {
+        burst_limit: 2,
+        initial_capacity: 0,
+        ..small_config(false)
+    };
+
+    let mut data: Vec<_> = data.into_iter()
+        .map(|s| s.chars().take(3).collect::<String>())
+        .collect();
+    data.extend(dense_keys("ab", 3));
+
+    let mut trie = BurstTrie::with_config(&config);
+    trie.extend(data.clone());
+
+    data.sort();
+
+    for (lower, upper) in [(lower.as_str(), upper.as_str()), ("a", "b"), ("ab", "abb"), ("", "a")] {
+        let expected: Vec<_> = data.iter().filter(|s| lower <= s.as_str()).collect();
+        assert_eq!(expected, trie.range(lower..).collect::<Vec<_>>());
+
+        let expected: Vec<_> = data.iter().filter(|s| s.as_str() <= upper).collect();
+        assert_eq!(expected, trie.range(..=upper).collect::<Vec<_>>());
+
+        let expected: Vec<_> = data.iter()
+            .filter(|s| lower <= s.as_str() && s.as_str() < upper)
+            .collect();
+        assert_eq!(expected, trie.range(lower..upper).collect::<Vec<_>>());
+    }
+}
+
+#[quickcheck]
+fn check_burst_trie_longest_prefix_match(data: Vec<String>, key: String) {
+    let config = BurstConfig {
+        burst_limit: 2,
+        initial_capacity: 0,
+        ..small_config(false)
+    };
+
+    let mut data: Vec<_> = data.into_iter()
+        .map(|s| s.chars().take(3).collect::<String>())
+        .collect();
+    data.extend(dense_keys("ab", 3).into_iter().filter(|s| s.len() != 2));
+
+    let trie: BurstTrie<_, _> = {
+        let mut trie = BurstTrie::with_config(&config);
+        trie.extend(data.clone());
+        trie
+    };
+
+    for key in [key.as_str(), "", "a", "ab", "abab", "abba", "ba", "c"] {
+        let expected = data.iter()
+            .filter(|s| key.starts_with(s.as_str()))
+            .max_by_key(|s| s.len());
+
+        assert_eq!(expected, trie.longest_prefix_match(key));
+    }
+}
+
+#[quickcheck]
+fn check_burst_trie_remove(data: Vec<String>, removed: Vec<String>, more: Vec<String>) {
+    let config = BurstConfig {
+        burst_limit: 2,
+        initial_capacity: 0,
+        ..small_config(false)
+    };
+
+    let mut data: Vec<_> = data.into_iter()
+        .map(|s| s.chars().take(3).collect::<String>())
+        .collect();
+    data.extend(dense_keys("ab", 3));
+
+    let mut trie = BurstTrie::with_config(&config);
+    trie.extend(data.clone());
+
+    data.sort();
+
+    // removes both missing keys and keys which are present, until the trie collapses
+    for key in removed.iter().chain(dense_keys("ab", 3).iter()) {
+        let expected = data.iter().position(|s| s == key).map(|i| data.remove(i));
+
+        assert_eq!(expected, trie.remove(key));
+        assert_eq!(data.len(), trie.len());
+    }
+
+    assert_eq!(data.iter().collect::<Vec<_>>(), trie.iter().collect::<Vec<_>>());
+
+    // the trie bursts again as items are added back
+    trie.extend(more.clone());
+    data.extend(more);
+    data.sort();
+
+    assert_eq!(data, trie.into_sorted_vec());
+}
+
+#[quickcheck]
+fn check_burst_trie_retain_drain(data: Vec<String>, lower: String, upper: String) {
+    let config = BurstConfig {
+        burst_limit: 2,
+        initial_capacity: 0,
+        ..small_config(false)
+    };
+
+    let mut data: Vec<_> = data.into_iter()
+        .map(|s| s.chars().take(3).collect::<String>())
+        .collect();
+    data.extend(dense_keys("abc", 3));
+
+    let mut trie = BurstTrie::with_config(&config);
+    trie.extend(data.clone());
+
+    data.sort();
+
+    trie.retain(|s| !s.ends_with('b'));
+    data.retain(|s| !s.ends_with('b'));
+
+    assert_eq!(data.len(), trie.len());
+    assert_eq!(data.iter().collect::<Vec<_>>(), trie.iter().collect::<Vec<_>>());
+
+    for (lower, upper) in [("ab", "ac"), ("", "a"), (lower.as_str(), upper.as_str()), ("", "d")] {
+        let (expected, rest) = data.into_iter()
+            .partition(|s| lower <= s.as_str() && s.as_str() < upper);
+        data = rest;
+
+        assert_eq!(expected, trie.drain_range(lower..upper));
+        assert_eq!(data.len(), trie.len());
+        assert_eq!(data.iter().collect::<Vec<_>>(), trie.iter().collect::<Vec<_>>());
+    }
+}
+
+#[quickcheck]
+fn check_burst_map_retain(entries: Vec<(String, u8)>, lower: String, upper: String) {
+    let config = BurstConfig {
+        burst_limit: 2,
+        initial_capacity: 0,
+        ..small_config(false)
+    };
+
+    let mut map = BurstMap::with_config(&config);
+    map.extend(entries.clone());
+    let mut expected: BTreeMap<_, _> = entries.into_iter().collect();
+
+    map.retain(|_, v| {
+        *v = v.wrapping_add(1);
+        *v % 2 == 0
+    });
+    expected.retain(|_, v| {
+        *v = v.wrapping_add(1);
+        *v % 2 == 0
+    });
+
+    assert_eq!(expected.iter().collect::<Vec<_>>(), map.iter().collect::<Vec<_>>());
+
+    if lower <= upper {
+        let drained: Vec<_> = expected.range(lower.clone()..upper.clone())
+            .map(|(k, v)| (k.clone(), *v))
+            .collect();
+        expected.retain(|k, _| !(lower <= *k && *k < upper));
+
+        assert_eq!(drained, map.drain_range(lower..upper));
+        assert_eq!(expected.len(), map.len());
+        assert_eq!(expected.iter().collect::<Vec<_>>(), map.iter().collect::<Vec<_>>());
+    }
+}
+
+#[quickcheck]
+fn check_burst_trie_append(left: Vec<String>, right: Vec<String>) {
+    let config = BurstConfig {
+        burst_limit: 2,
+        initial_capacity: 0,
+        ..small_config(false)
+    };
+
+    // tag each item with the trie it came from, to check that equal items keep their order
+    let tag = |data: Vec<String>, tag: &str| -> Vec<String> {
+        data.into_iter().map(|s| s.chars().take(3).collect::<String>() + "\0" + tag).collect()
+    };
+
+    let mut left = tag(left, "a");
+    let mut right = tag(right, "b");
+    left.extend(tag(dense_keys("ab", 2), "a"));
+    right.extend(tag(dense_keys("abc", 3), "b"));
+
+    let mut trie = BurstTrie::with_config(&config);
+    trie.extend(left.clone());
+    let mut other = BurstTrie::with_config(&config);
+    other.extend(right.clone());
+
+    trie.append(&mut other);
+
+    let mut expected = left;
+    expected.extend(right);
+    expected.sort();
+
+    assert!(other.is_empty());
+    assert_eq!(expected.len(), trie.len());
+    assert_eq!(expected, trie.into_sorted_vec());
+}
+
+#[quickcheck]
+fn check_sorter_append(left: Vec<String>, right: Vec<String>) {
+    let config = BurstConfig {
+        burst_limit: 2,
+        initial_capacity: 0,
+        ..small_config(false)
+    };
+
+    let mut sorter = BurstSorter::new(&config);
+    sorter.extend(left.clone());
+    let mut other = BurstSorter::new(&config);
+    other.extend(right.clone());
+
+    sorter.append(&mut other);
+
+    let mut expected = left;
+    expected.extend(right);
+    expected.sort();
+
+    assert_eq!(expected, sorter.into_sorted_iter().collect::<Vec<_>>());
+    assert_eq!(0, other.into_sorted_iter().count());
+}
+
+#[quickcheck]
+fn check_burst_trie_set_ops(left: Vec<String>, right: Vec<String>) {
+    let config = BurstConfig {
+        burst_limit: 2,
+        initial_capacity: 0,
+        ..small_config(false)
+    };
+
+    let mut left: Vec<_> = left.into_iter()
+        .map(|s| s.chars().take(3).collect::<String>())
+        .collect();
+    let mut right: Vec<_> = right.into_iter()
+        .map(|s| s.chars().take(3).collect::<String>())
+        .collect();
+    left.extend(dense_keys("ab", 3));
+    right.extend(dense_keys("bc", 2));
+
+    let build = |data: &[String]| {
+        let mut trie = BurstTrie::with_config(&config);
+        trie.extend(data.iter().cloned());
+        trie
+    };
+
+    let left_set: BTreeSet<_> = left.iter().cloned().collect();
+    let right_set: BTreeSet<_> = right.iter().cloned().collect();
+
+    assert_eq!(left_set.union(&right_set).cloned().collect::<Vec<_>>(),
+               build(&left).union(build(&right)));
+    assert_eq!(left_set.intersection(&right_set).cloned().collect::<Vec<_>>(),
+               build(&left).intersection(build(&right)));
+    assert_eq!(left_set.difference(&right_set).cloned().collect::<Vec<_>>(),
+               build(&left).difference(build(&right)));
+}
+
+fn expected_counts(data: &[String]) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+
+    for x in data {
+        *counts.entry(x.clone()).or_insert(0) += 1;
+    }
+
+    counts
+}
+
+#[quickcheck]
+fn check_burst_counter(data: Vec<String>, more: Vec<String>, n: usize) {
+    let config = BurstConfig {
+        burst_limit: 2,
+        initial_capacity: 0,
+        ..small_config(false)
+    };
+
+    let mut data: Vec<_> = data.into_iter()
+        .map(|s| s.chars().take(2).collect::<String>())
+        .collect();
+    data.extend(dense_keys("ab", 3));
+    data.extend(dense_keys("a", 4));
+
+    let mut counter = BurstCounter::with_config(&config);
+    counter.extend(data.clone());
+
+    let mut other = BurstCounter::with_config(&config);
+    other.extend(more.clone());
+    counter.append(&mut other);
+    data.extend(more);
+
+    let expected = expected_counts(&data);
+
+    assert!(other.is_empty());
+    assert_eq!(expected.len(), counter.len());
+    assert_eq!(expected.iter().map(|(k, &v)| (k, v)).collect::<Vec<_>>(),
+               counter.iter().collect::<Vec<_>>());
+
+    for x in &data {
+        assert_eq!(expected[x], counter.count(x));
+    }
+
+    let mut most_common: Vec<_> = expected.iter().map(|(k, &v)| (k, v)).collect();
+    most_common.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+    most_common.truncate(n % 8);
+
+    assert_eq!(most_common, counter.most_common(n % 8));
+}
+
+#[cfg(feature = "parallelization")]
+#[quickcheck]
+fn check_par_burst_counter(mut data: Vec<String>) {
+    let config = BurstConfig {
+        burst_limit: 2,
+        initial_capacity: 0,
+        ..small_config(false)
+    };
+
+    for _ in 0..4 {
+        data.extend(dense_keys("abc", 3));
+    }
+
+    let counter = BurstCounter::par_from_vec(data.clone(), &config);
+
+    assert_eq!(expected_counts(&data).into_iter().collect::<Vec<_>>(), counter.into_sorted_vec());
+}
+
+fn external_sort(data: &[Vec<u8>], config: &BurstConfig, max_memory: usize) -> Vec<Vec<u8>> {
+    let dir = std::env::temp_dir()
+        .join(format!("burstsort-test-{}-{}", std::process::id(), unique_id()));
+    std::fs::create_dir(&dir).unwrap();
+
+    let mut sorter = ExternalSorter::in_dir(config, max_memory, &dir);
+
+    for x in data {
+        sorter.insert(x.clone()).unwrap();
+    }
+
+    let mut sorted = Vec::new();
+    sorter.finish(|x| sorted.push(x)).unwrap();
+
+    // every spill file is removed once sorted
+    assert_eq!(0, std::fs::read_dir(&dir).unwrap().count());
+    std::fs::remove_dir(&dir).unwrap();
+
+    sorted
+}
+
+fn unique_id() -> usize {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    NEXT.fetch_add(1, Ordering::Relaxed)
+}
+
+#[quickcheck]
+fn check_external_sort(data: Vec<Vec<u8>>, max_memory: u16) {
+    let config = BurstConfig {
+        initial_capacity: 0,
+        ..small_config(false)
+    };
+
+    let mut data = data;
+    data.extend(dense_keys("abc", 4).into_iter().map(String::into_bytes));
+
+    let mut expected = data.clone();
+    expected.sort();
+
+    assert_eq!(expected, external_sort(&data, &config, max_memory as usize));
+}
+
+#[test]
+fn check_external_sort_unburst_root() {
+    let dir = std::env::temp_dir()
+        .join(format!("burstsort-test-{}-{}", std::process::id(), unique_id()));
+    std::fs::create_dir(&dir).unwrap();
+
+    // far fewer items than the burst limit, but far more bytes than the budget
+    let max_memory = 64 * 1024;
+    let data: Vec<_> = (0..10_000u32)
+        .map(|i| format!("{:0>240}", i.wrapping_mul(2_654_435_761)).into_bytes())
+        .collect();
+
+    let mut sorter = ExternalSorter::in_dir(&UTF8_CONFIG, max_memory, &dir);
+
+    for x in &data {
+        sorter.insert(x.clone()).unwrap();
+        assert!(sorter.memory_used() <= max_memory);
+    }
+
+    assert!(std::fs::read_dir(&dir).unwrap().count() > 0);
+
+    let mut sorted = Vec::new();
+    sorter.finish(|x| sorted.push(x)).unwrap();
+    std::fs::remove_dir(&dir).unwrap();
+
+    let mut expected = data;
+    expected.sort();
+
+    assert_eq!(expected, sorted);
+}
+
+/// Inserts alternating lines of a repeated request and of a handful of others, checking the
+/// budget after every insert. Returns the time taken.
+fn external_sort_repeats(n: usize, max_memory: usize) -> std::time::Duration {
+    let dir = std::env::temp_dir()
+        .join(format!("burstsort-test-{}-{}", std::process::id(), unique_id()));
+    std::fs::create_dir(&dir).unwrap();
+
+    let data: Vec<_> = (0..n).map(|i| match i % 2 {
+        0 => b"GET /index.html".to_vec(),
+        _ => format!("GET /{}", i % 7).into_bytes(),
+    }).collect();
+
+    let start = std::time::Instant::now();
+    let mut sorter = ExternalSorter::in_dir(&UTF8_CONFIG, max_memory, &dir);
+
+    for x in &data {
+        sorter.insert(x.clone()).unwrap();
+        assert!(sorter.memory_used() <= max_memory);
+    }
+
+    let elapsed = start.elapsed();
+
+    let mut sorted = Vec::new();
+    sorter.finish(|x| sorted.push(x)).unwrap();
+    std::fs::remove_dir(&dir).unwrap();
+
+    let mut expected = data;
+    expected.sort();
+    assert_eq!(expected, sorted);
+
+    elapsed
+}
+
+#[test]
+fn check_external_sort_repeats() {
+    // repeated keys end at trie nodes rather than in buckets, and must be spilled all the same
+    // best of a few runs, so that other tests running at the same time matter less
+    let time = |n| (0..3).map(|_| external_sort_repeats(n, 100_000)).min().unwrap();
+    let small = time(10_000);
+    let large = time(80_000);
+
+    // eight times the items take about eight times as long, rather than sixty-four times
+    assert!(large < small * 24, "{:?} for 10k inserts, {:?} for 80k", small, large);
+
+    // a spilled bucket holding copies of its own prefix is sorted externally again
+    let mut data: Vec<_> = (0..5_000u32)
+        .map(|i| format!("a{}", i.wrapping_mul(2_654_435_761)).into_bytes())
+        .collect();
+    data.extend(std::iter::repeat_n(b"a".to_vec(), 5_000));
+
+    let mut expected = data.clone();
+    expected.sort();
+
+    assert_eq!(expected, external_sort(&data, &UTF8_CONFIG, 4096));
+}
+
+#[test]
+fn check_external_sort_nested() {
+    let config = BurstConfig {
+        initial_capacity: 0,
+        ..small_config(false)
+    };
+
+    // a bucket which keeps growing after being spilled has to be sorted externally again
+    let mut data: Vec<_> = (0..20_000).rev()
+        .map(|i: u32| format!("prefix-{}", i.wrapping_mul(2_654_435_761)).into_bytes())
+        .collect();
+    data.extend(dense_keys("ab", 6).into_iter().map(String::into_bytes));
+
+    let mut expected = data.clone();
+    expected.sort();
+
+    assert_eq!(expected, external_sort(&data, &config, 4096));
+}
+
+#[quickcheck]
+fn check_sort_memory_limit(mut data: Vec<String>, max_memory: u16) {
+    let config = BurstConfig {
+        max_memory: Some(max_memory as usize),
+        ..small_config(false)
+    };
+
+    data.extend(dense_keys("abc", 4));
+
+    let mut expected = data.clone();
+    expected.sort();
+
+    burstsort(&mut data, &config);
+
+    assert_eq!(expected, data);
+}
+
+#[test]
+fn check_burst_trie_memory_churn() {
+    let keys: Vec<_> = (0..50_000u32)
+        .map(|i| i.wrapping_mul(2_654_435_761).to_string())
+        .collect();
+
+    let mut trie = BurstTrie::with_config(&COLLECTION_CONFIG);
+    let fresh = trie.memory_used();
+
+    // emptied nodes give back everything they were counted for, round after round
+    for _ in 0..3 {
+        trie.extend(keys.iter().cloned());
+
+        for x in &keys {
+            assert!(trie.remove(x).is_some());
+        }
+
+        assert!(trie.is_empty());
+        assert!(trie.memory_used() < fresh + 16 * 1024);
+    }
+}
+
 #[quickcheck]
-fn check_sort_string(mut data: Vec<String>) {
+fn check_memory_count(data: Vec<String>, removed: Vec<String>, more: Vec<String>) {
+    let unlimited = BurstConfig {
+        burst_limit: 4,
+        initial_capacity: 2,
+        ..small_config(false)
+    };
+
+    // the limit is never reached, so the trie is built the same, but keeps count as it goes
+    let counted = BurstConfig { max_memory: Some(usize::MAX), ..unlimited };
+    let blocks = BurstConfig { block_buckets: true, ..unlimited };
+    let counted_blocks = BurstConfig { block_buckets: true, ..counted };
+
+    // shared prefixes are skipped, and split as other keys leave them
+    let mut data = data;
+    data.extend(dense_keys("ab", 3).into_iter().map(|s| format!("https://example.com/{}", s)));
+
+    let ordered = |config| {
+        let mut used = Vec::new();
+        let mut trie = BurstTrie::with_config(config);
+        trie.extend(data.iter().cloned());
+        used.push(trie.memory_used());
+
+        for x in &removed {
+            trie.remove(x);
+        }
+
+        used.push(trie.memory_used());
+        trie.retain(|x| x.len() % 2 == 0);
+        used.push(trie.memory_used());
+        trie.drain_range("https://example.com/b"..);
+        used.push(trie.memory_used());
+
+        let mut other = BurstTrie::with_config(config);
+        other.extend(more.iter().cloned());
+        trie.append(&mut other);
+        used.extend([trie.memory_used(), other.memory_used(), trie.clone().memory_used()]);
+
+        let mut counter = BurstCounter::with_config(config);
+        let mut other = BurstCounter::with_config(config);
+        counter.extend(data.iter().cloned());
+        other.extend(more.iter().cloned());
+        counter.append(&mut other);
+        used.extend([counter.memory_used(), other.memory_used()]);
+        used
+    };
+
+    assert_eq!(ordered(&unlimited), ordered(&counted));
+
+    let unordered = |config| {
+        let mut used = Vec::new();
+        let mut sorter = BurstSorter::new(config);
+        let mut other = BurstSorter::new(config);
+        sorter.extend(data.iter().cloned());
+        other.extend(more.iter().cloned());
+        sorter.append(&mut other);
+        used.extend([sorter.memory_used(), other.memory_used()]);
+
+        let mut batch = more.clone();
+        sorter.sort(&mut batch);
+        used.push(sorter.memory_used());
+        used
+    };
+
+    assert_eq!(unordered(&unlimited), unordered(&counted));
+    assert_eq!(unordered(&blocks), unordered(&counted_blocks));
+}
+
+#[quickcheck]
+fn check_burst_trie_sparse(data: Vec<String>, other: Vec<String>, lower: String, upper: String) {
+    // tables are sparse from the very first burst
     let config = BurstConfig {
-        burst_limit: 8,
-        initial_capacity: 4,
-        classes: 256,
-        hint_long: false
+        burst_limit: 2,
+        initial_capacity: 0,
+        max_memory: Some(0),
+        ..small_config(false)
+    };
+
+    let shorten = |data: Vec<String>| -> Vec<String> {
+        data.into_iter().map(|s| s.chars().take(3).collect()).collect()
+    };
+
+    let mut data = shorten(data);
+    let mut other = shorten(other);
+    data.extend(dense_keys("ab", 3));
+    other.extend(dense_keys("bc", 3));
+
+    let build = |data: &[String]| {
+        let mut trie = BurstTrie::with_config(&config);
+        trie.extend(data.iter().cloned());
+        trie
+    };
+
+    let left_set: BTreeSet<_> = data.iter().cloned().collect();
+    let right_set: BTreeSet<_> = other.iter().cloned().collect();
+
+    assert_eq!(left_set.union(&right_set).cloned().collect::<Vec<_>>(),
+               build(&data).union(build(&other)));
+    assert_eq!(left_set.difference(&right_set).cloned().collect::<Vec<_>>(),
+               build(&data).difference(build(&other)));
+
+    let mut trie = build(&data);
+    trie.append(&mut build(&other));
+    data.extend(other);
+    data.sort();
+
+    assert_eq!(data.iter().collect::<Vec<_>>(), trie.iter().collect::<Vec<_>>());
+    assert_eq!(data.iter().filter(|s| s.starts_with('b')).count(), trie.count_prefix("b"));
+
+    if lower <= upper {
+        let expected: Vec<_> = data.iter()
+            .filter(|s| lower <= **s && **s < upper)
+            .cloned()
+            .collect();
+
+        assert_eq!(expected.iter().collect::<Vec<_>>(),
+                   trie.range(lower.as_str()..upper.as_str()).collect::<Vec<_>>());
+        assert_eq!(expected, trie.drain_range(lower.as_str()..upper.as_str()));
+
+        data.retain(|s| !(lower <= *s && *s < upper));
+    }
+
+    for key in dense_keys("abc", 2) {
+        let expected = data.iter().position(|s| *s == key).map(|i| data.remove(i));
+        assert_eq!(expected, trie.remove(&key));
+    }
+
+    assert_eq!(data.len(), trie.len());
+    assert_eq!(data, trie.into_sorted_vec());
+}
+
+#[test]
+fn check_memory_limit_saves_memory() {
+    let config = small_config(false);
+    let limited = BurstConfig {
+        max_memory: Some(64 * 1024),
+        ..config
+    };
+
+    let data = dense_keys("abcd", 6);
+
+    let mut sorter = BurstSorter::new(&config);
+    sorter.extend(data.iter().map(String::as_str));
+    let mut lean = BurstSorter::new(&limited);
+    lean.extend(data.iter().map(String::as_str));
+
+    assert!(lean.memory_used() < sorter.memory_used() / 4);
+
+    let mut expected = data.clone();
+    expected.sort();
+
+    assert_eq!(expected, lean.into_sorted_iter().collect::<Vec<_>>());
+}
+
+#[quickcheck]
+fn check_sorter_reuse(batches: Vec<Vec<String>>, max_retained: Option<u16>) {
+    let config = small_config(false);
+
+    let mut sorter = BurstSorter::new(&config);
+    sorter.set_max_retained(max_retained.map(usize::from));
+
+    for mut batch in batches.into_iter().chain(std::iter::once(dense_keys("abc", 4))) {
+        let mut expected = batch.clone();
+        expected.sort();
+
+        sorter.sort(&mut batch);
+
+        assert_eq!(expected, batch);
+    }
+
+    // a trie which grew too large is replaced by a fresh one
+    let fresh = BurstSorter::<String, _>::new(&config).memory_used();
+
+    if let Some(max_retained) = max_retained {
+        assert!(sorter.memory_used() <= fresh.max(max_retained as usize));
+    }
+}
+
+#[quickcheck]
+fn check_sort_block_buckets(mut data: Vec<String>, more: Vec<String>, k: usize) {
+    // first blocks are smaller than the burst limit, so buckets span several blocks
+    let config = BurstConfig {
+        burst_limit: 32,
+        initial_capacity: 0,
+        block_buckets: true,
+        ..small_config(false)
     };
 
+    data.extend(dense_keys("abc", 4));
+
     let mut expected = data.clone();
+    expected.extend(more.iter().cloned());
+    expected.sort();
+
+    let mut sorter = BurstSorter::new(&config);
+    sorter.extend(data.clone());
+    let mut other = BurstSorter::new(&config);
+    other.extend(more.clone());
+
+    sorter.append(&mut other);
+
+    assert_eq!(expected, sorter.into_sorted_iter().collect::<Vec<_>>());
+
+    data.extend(more);
+
+    let mut top_k = data.clone();
+    burstsort_top_k(&mut top_k, k, &config);
+
+    assert_eq!(expected[..k.min(expected.len())], top_k[..]);
 
     burstsort(&mut data, &config);
 
+    assert_eq!(expected, data);
+}
+
+#[cfg(feature = "parallelization")]
+#[quickcheck]
+fn check_par_sort_block_buckets(mut data: Vec<String>) {
+    let config = BurstConfig {
+        burst_limit: 32,
+        initial_capacity: 0,
+        block_buckets: true,
+        ..small_config(false)
+    };
+
+    data.extend(dense_keys("abc", 4));
+
+    let mut expected = data.clone();
     expected.sort();
 
+    par_burstsort(&mut data, &config);
+
     assert_eq!(expected, data);
 }
 
+#[test]
+fn check_burst_bytes_threshold() {
+    let config = BurstConfig {
+        burst_limit: usize::MAX,
+        initial_capacity: 0,
+        burst_bytes: Some(16),
+        ..small_config(false)
+    };
+
+    // four keys hold exactly 16 bytes, and the fifth pushes the root over and bursts it
+    let mut trie = BurstTrie::with_config(&config);
+    trie.extend(["abcd", "abce", "abcf", "abcg"]);
+
+    let table = 256 * std::mem::size_of::<usize>();
+    let before = trie.memory_used();
+    assert!(before < table);
+
+    trie.insert("abch");
+    assert!(trie.memory_used() >= before + table);
+}
+
 #[quickcheck]
-fn check_sort_string_long(mut data: Vec<String>) {
+fn check_burst_bytes(mut data: Vec<String>, removed: Vec<String>) {
+    // buckets only burst once they hold more than 16 bytes past their prefix
+    let config = BurstConfig {
+        burst_limit: usize::MAX,
+        initial_capacity: 0,
+        burst_bytes: Some(16),
+        ..small_config(false)
+    };
+
+    data.extend(dense_keys("abc", 4));
+
+    let mut trie = BurstTrie::with_config(&config);
+    trie.extend(data.clone());
+
+    // without the byte limit, nothing bursts and no tables are allocated
+    let unlimited_config = BurstConfig { burst_bytes: None, ..config };
+    let mut unlimited = BurstTrie::with_config(&unlimited_config);
+    unlimited.extend(data.clone());
+    assert!(trie.memory_used() > unlimited.memory_used());
+
+    let mut expected = data.clone();
+    expected.sort();
+
+    let mut arena_sorted = data.clone();
+    arena_burstsort(&mut arena_sorted, &config);
+    assert_eq!(expected, arena_sorted);
+
+    burstsort(&mut data, &config);
+
+    assert_eq!(expected, data);
+
+    for key in removed.iter().chain(dense_keys("abc", 4).iter()) {
+        let index = expected.iter().position(|s| s == key);
+
+        assert_eq!(index.map(|i| expected.remove(i)), trie.remove(key));
+    }
+
+    assert_eq!(expected.iter().collect::<Vec<_>>(), trie.iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn check_burst_limit_at() {
     let config = BurstConfig {
         burst_limit: 8,
-        initial_capacity: 4,
-        classes: 256,
-        hint_long: true
+        ..UTF8_CONFIG
+    };
+
+    assert_eq!(8, config.burst_limit_at(0));
+    assert_eq!(8, config.burst_limit_at(100));
+
+    let scheduled = BurstConfig {
+        burst_schedule: Some(|level| [1, 4, 16][level.min(2)]),
+        ..config
+    };
+
+    assert_eq!([1, 4, 16, 16, 16], [0, 1, 2, 3, 100].map(|level| scheduled.burst_limit_at(level)));
+}
+
+#[test]
+fn check_burst_schedule() {
+    let config = BurstConfig {
+        burst_limit: 2,
+        initial_capacity: 0,
+        burst_schedule: Some(|level| [1, 4, 16][level.min(2)]),
+        ..small_config(false)
+    };
+
+    let mut trie = BurstTrie::with_config(&config);
+    let table = 256 * std::mem::size_of::<usize>();
+
+    // whether inserting the key bursts a node, allocating a table
+    let mut bursts = |key: String| {
+        let before = trie.memory_used();
+        trie.insert(key);
+        trie.memory_used() >= before + table
+    };
+
+    // keys go down the same path, and part at the level of each node so that no symbols are
+    // skipped, so the node at level n bursts past schedule[n] items
+    let mut keys = vec!["ba".to_string()];
+    keys.extend(('a'..='e').map(|c| format!("a{}", c)));
+    keys.extend(('a'..='q').map(|c| format!("ab{}", c)));
+    let burst: Vec<_> = keys.iter().map(|x| bursts(x.clone())).collect();
+
+    let expected: Vec<_> = (0..keys.len()).map(|i| [1, 5, 22].contains(&i)).collect();
+    assert_eq!(expected, burst);
+
+    keys.sort();
+
+    let mut data = keys.clone();
+    arena_burstsort(&mut data, &config);
+    assert_eq!(keys, data);
+    assert!(trie.iter().eq(&keys));
+}
+
+#[quickcheck]
+fn check_path_compression(data: Vec<String>, other: Vec<String>) {
+    let config = BurstConfig {
+        burst_limit: 4,
+        initial_capacity: 0,
+        ..small_config(false)
+    };
+
+    // keys share a long prefix, and some end along it or leave it part way
+    let prefix = "https://example.com/a/b/";
+    let with_prefix = |keys: Vec<String>| -> Vec<String> {
+        let mut keys: Vec<_> = keys.into_iter().map(|s| format!("{}{}", prefix, s)).collect();
+        keys.extend(dense_keys("ab", 3).into_iter().map(|s| format!("{}{}", prefix, s)));
+        keys
+    };
+
+    let mut data = with_prefix(data);
+    let mut other = with_prefix(other);
+    let urls = ["https://", "https://example.org/", "https://example.com/a/b/"];
+    other.extend(urls.map(String::from));
+
+    let mut expected = data.clone();
+    expected.extend(other.iter().cloned());
+    expected.sort();
+
+    let mut sorter = BurstSorter::new(&config);
+    sorter.extend(data.clone());
+
+    let mut more = BurstSorter::new(&config);
+    more.extend(other.clone());
+    sorter.append(&mut more);
+
+    assert_eq!(expected, sorter.into_sorted_iter().collect::<Vec<_>>());
+
+    data.extend(other);
+
+    let mut sorted = data.clone();
+    let lcps = burstsort_with_lcp(&mut sorted, &config);
+
+    assert_eq!(expected, sorted);
+    assert_eq!(expected_lcps(&expected), lcps);
+}
+
+#[quickcheck]
+fn check_ordered_path_compression(data: Vec<String>, other: Vec<String>, query: String) {
+    let config = BurstConfig {
+        burst_limit: 4,
+        initial_capacity: 0,
+        ..small_config(false)
+    };
+
+    let prefix = "https://example.com/a/b/";
+    let with_prefix = |keys: Vec<String>| -> Vec<String> {
+        let mut keys: Vec<_> = keys.into_iter()
+            .map(|s| format!("{}{}", prefix, s.chars().take(3).collect::<String>()))
+            .collect();
+        keys.extend(dense_keys("ab", 3).into_iter().map(|s| format!("{}{}", prefix, s)));
+        keys
+    };
+
+    let data = with_prefix(data);
+    let mut other = with_prefix(other);
+    other.extend(["https://", "https://example.org/", prefix].map(String::from));
+
+    let mut trie = BurstTrie::with_config(&config);
+    trie.extend(data.clone());
+
+    let mut sorted = data.clone();
+    sorted.sort();
+    assert!(trie.iter().eq(&sorted));
+
+    // queries which end along the skipped prefix, leave it, or follow it
+    let query = format!("{}{}", prefix, query.chars().take(3).collect::<String>());
+    let queries = [query.as_str(), "", "https://example.com/a", "https://example.com/a/c",
+                   "https://example.com/a/b/", "https://example.com/a/b/ab", "https://f"];
+
+    for &x in &queries {
+        let expected: Vec<_> = sorted.iter().filter(|s| s.starts_with(x)).collect();
+        assert_eq!(expected, trie.iter_prefix(x).collect::<Vec<_>>());
+        assert_eq!(expected.len(), trie.count_prefix(x));
+
+        let expected = sorted.iter().filter(|s| x.starts_with(s.as_str())).max_by_key(|s| s.len());
+        assert_eq!(expected, trie.longest_prefix_match(x));
+
+        for &y in &queries {
+            let expected: Vec<_> = sorted.iter()
+                .filter(|s| x <= s.as_str() && s.as_str() <= y)
+                .collect();
+            assert_eq!(expected, trie.range(x..=y).collect::<Vec<_>>());
+
+            let expected: Vec<_> = sorted.iter()
+                .filter(|s| x < s.as_str() && s.as_str() < y)
+                .collect();
+            let excluded = (Bound::Excluded(x), Bound::Excluded(y));
+            assert_eq!(expected, trie.range::<&str, _>(excluded).collect::<Vec<_>>());
+        }
+    }
+
+    let mut map = BurstMap::with_config(&config);
+
+    for (i, x) in data.iter().enumerate() {
+        map.insert(x.clone(), i);
+    }
+
+    for x in data.iter().chain(&other).map(String::as_str).chain(queries) {
+        let expected = data.iter().rposition(|y| y == x);
+        assert_eq!(expected.as_ref(), map.get(x));
+    }
+
+    let mut copy = trie.clone();
+
+    for x in &other {
+        match sorted.iter().position(|y| y == x) {
+            Some(index) => assert_eq!(Some(sorted.remove(index)), copy.remove(x)),
+            None => assert_eq!(None, copy.remove(x)),
+        }
+    }
+
+    assert!(copy.iter().eq(&sorted));
+
+    let mut more = BurstTrie::with_config(&config);
+    more.extend(other.clone());
+
+    let mut expected: Vec<_> = data.iter().chain(&other).cloned().collect();
+    expected.sort();
+    expected.dedup();
+    assert_eq!(expected, trie.clone().union(more.clone()));
+
+    let mut expected: Vec<_> = data.iter().chain(&other).cloned().collect();
+    expected.sort();
+    trie.append(&mut more);
+    assert!(trie.iter().eq(&expected));
+}
+
+#[test]
+fn check_ordered_path_compression_memory() {
+    // routing tables hold long keys which share most of their symbols
+    let keys: Vec<_> = (0..5_000)
+        .map(|i| format!("https://example.com/api/v1/users/{}", i))
+        .collect();
+
+    let mut sorter = BurstSorter::new(&COLLECTION_CONFIG);
+    sorter.extend(keys.iter().cloned());
+
+    let mut trie = BurstTrie::with_config(&COLLECTION_CONFIG);
+    trie.extend(keys.iter().cloned());
+
+    let mut map = BurstMap::with_config(&COLLECTION_CONFIG);
+    map.extend(keys.iter().cloned().map(|x| (x, ())));
+
+    let mut counter = BurstCounter::with_config(&COLLECTION_CONFIG);
+    counter.extend(keys.iter().cloned());
+
+    // the shared prefix costs a single table rather than one per symbol
+    let budget = 2 * sorter.memory_used();
+    assert!(trie.memory_used() < budget, "{} over {}", trie.memory_used(), budget);
+    assert!(map.memory_used() < budget, "{} over {}", map.memory_used(), budget);
+    assert!(counter.memory_used() < budget, "{} over {}", counter.memory_used(), budget);
+
+    assert!(keys.iter().all(|x| map.get(x).is_some() && counter.count(x) == 1));
+    assert_eq!(Some(&keys[42]), trie.longest_prefix_match(&format!("{}/posts", keys[42])));
+    assert_eq!(keys.len(), trie.count_prefix("https://example.com/api/"));
+}
+
+/// Keys sharing a 100k-byte prefix, with some of them ending along it.
+fn deep_keys(data: Vec<String>) -> Vec<String> {
+    let prefix = "x".repeat(100_000);
+    let mut keys: Vec<_> = data.into_iter().map(|s| format!("{}{}", prefix, s)).collect();
+
+    keys.extend(dense_keys("xy", 2).into_iter().map(|s| format!("{}{}", prefix, s)));
+    keys.extend([1, 5_000, 50_000, 99_999].map(|i| prefix[..i].to_string()));
+    keys
+}
+
+/// Keys ending in "b" after every number of "a"s, which branch off one level apart.
+fn staircase_keys() -> Vec<String> {
+    (0..5_000).rev().map(|i| format!("{}b", "a".repeat(i))).collect()
+}
+
+#[quickcheck]
+fn check_deep_prefix(data: Vec<String>) {
+    let config = BurstConfig {
+        burst_limit: 2,
+        initial_capacity: 0,
+        ..small_config(true)
+    };
+
+    let mut data = deep_keys(data);
+    let mut expected = data.clone();
+    expected.sort();
+
+    let mut top = data.clone();
+    burstsort_top_k(&mut top, 3, &config);
+    assert_eq!(expected[..3], top[..]);
+
+    burstsort(&mut data, &config);
+    assert_eq!(expected, data);
+}
+
+#[test]
+fn check_deep_trie() {
+    // sparse tables, so that a node per level of the prefix fits in memory
+    let config = BurstConfig {
+        burst_limit: 2,
+        initial_capacity: 0,
+        max_memory: Some(0),
+        ..small_config(true)
     };
 
+    let data = deep_keys(Vec::new());
     let mut expected = data.clone();
+    expected.sort();
+
+    let mut trie = BurstTrie::with_config(&config);
+    trie.extend(data.iter().cloned());
+
+    assert!(data.iter().all(|x| trie.longest_prefix_match(x) == Some(x)));
+    assert!(trie.iter().eq(&expected));
+
+    let mut copy = trie.clone();
+    assert!(copy.iter().eq(&expected));
+
+    let middle = "x".repeat(50_000);
+    let (lower, upper): (Vec<_>, Vec<_>) = expected.iter().cloned().partition(|x| *x < middle);
+    assert_eq!(upper, copy.drain_range(middle.as_str()..));
+    assert!(copy.iter().eq(&lower));
+
+    copy.retain(|x| x.len() % 2 == 0);
+    assert!(copy.iter().eq(lower.iter().filter(|x| x.len() % 2 == 0)));
+
+    for x in &lower {
+        assert_eq!(x.len() % 2 == 0, copy.remove(x).as_ref() == Some(x));
+    }
+
+    assert!(copy.is_empty());
+
+    let mut other = BurstTrie::with_config(&config);
+    other.extend(data.iter().cloned());
+    copy.append(&mut other);
+    assert!(copy.iter().eq(&expected));
+
+    let mut distinct = expected.clone();
+    distinct.dedup();
+    assert_eq!(distinct, copy.union(trie.clone()));
+
+    let mut map = BurstMap::with_config(&config);
+
+    for (i, x) in data.iter().enumerate() {
+        map.insert(x.clone(), i);
+    }
+
+    for x in &data {
+        *map.get_mut(x).unwrap() += 1;
+    }
+
+    let mut counter = BurstCounter::with_config(&config);
+    let mut more = BurstCounter::with_config(&config);
+    counter.extend(data.iter().cloned());
+    more.extend(data.iter().cloned());
+    counter.append(&mut more);
+
+    for (i, x) in data.iter().enumerate() {
+        assert!(map.get(x) > Some(&i));
+        assert_eq!(2 * data.iter().filter(|y| *y == x).count(), counter.count(x));
+    }
+
+    assert_eq!(expected, trie.into_sorted_vec());
 
+    let mut sorter = BurstSorter::new(&config);
+    sorter.extend(staircase_keys());
+
+    let mut expected = staircase_keys();
+    expected.sort();
+    assert_eq!(expected, sorter.into_sorted_iter().collect::<Vec<_>>());
+
+    let mut data = staircase_keys();
     burstsort(&mut data, &config);
+    assert_eq!(expected, data);
+
+    // arena tables are always dense, so only the staircase is shallow enough to fit in memory
+    let arena_config = BurstConfig {
+        classes: 128,
+        ..config
+    };
+
+    let mut data = staircase_keys();
+    arena_burstsort(&mut data, &arena_config);
+    assert_eq!(expected, data);
+}
+
+#[cfg(feature = "parallelization")]
+#[test]
+fn check_par_deep_trie() {
+    let config = BurstConfig {
+        burst_limit: 2,
+        initial_capacity: 0,
+        max_memory: Some(0),
+        ..small_config(true)
+    };
+
+    let mut data = staircase_keys();
+    let mut expected = data.clone();
+    expected.sort();
+
+    par_burstsort(&mut data, &config);
+    assert_eq!(expected, data);
+
+    let mut data = deep_keys(Vec::new());
+    let mut expected = data.clone();
+    expected.sort();
+
+    par_burstsort(&mut data, &config);
+    assert_eq!(expected, data);
+}
+
+#[quickcheck]
+fn check_short_keys(data: Vec<String>, block_buckets: bool) {
+    let config = BurstConfig {
+        burst_limit: 4,
+        initial_capacity: 2,
+        block_buckets,
+        ..small_config(false)
+    };
+
+    // keys ending at every level, each several times, so that bursts move many matches
+    let mut data: Vec<String> = data.into_iter()
+        .chain(dense_keys("abc", 3))
+        .flat_map(|s| vec![s; 3])
+        .collect();
 
+    let mut expected = data.clone();
     expected.sort();
 
+    let mut trie = BurstTrie::with_config(&config);
+    trie.extend(data.iter().cloned());
+    assert_eq!(expected, trie.into_sorted_vec());
+
+    let mut sorter = BurstSorter::new(&config);
+    sorter.extend(data.iter().cloned());
+    assert_eq!(expected, sorter.into_sorted_iter().collect::<Vec<_>>());
+
+    burstsort(&mut data, &config);
     assert_eq!(expected, data);
 }
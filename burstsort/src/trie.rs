@@ -1,10 +1,23 @@
 use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::iter::Peekable;
 use std::marker::PhantomData;
+use std::mem;
+use std::ops::Bound;
+use std::vec;
+
+use crate::blocks::Blocks;
+use crate::merge::{Append, MergeSink};
+use crate::spans::Span;
 
 #[cfg(feature = "parallelization")]
-use rayon::prelude::ParallelSliceMut;
+use rayon::prelude::{IntoParallelRefMutIterator, ParallelIterator, ParallelSliceMut};
 
 /// Tuning configuration for burstsort.
+///
+/// Options are added over time, so configs are built with [BurstConfig::new] and the `with_`
+/// methods, or from one of the crate's configs, rather than listing every field.
+#[non_exhaustive]
 pub struct BurstConfig {
     /// Threshold after which nodes are burst.
     pub burst_limit: usize,
@@ -14,171 +27,2546 @@ pub struct BurstConfig {
     pub classes: usize,
     /// Hints to the algorithm that items may be long.
     pub hint_long: bool,
+    /// Approximate limit on the bytes allocated by a trie for its nodes, matches and buckets.
+    /// Only the size of the items themselves is counted, not anything they point to.
+    ///
+    /// Once reached, the trie stops pre-allocating storage vectors, and bursts into sparse
+    /// tables which only hold the child nodes actually used.
+    ///
+    /// Tries only keep a running count with a limit set. Without one, the memory used is added
+    /// up over every node when asked for.
+    pub max_memory: Option<usize>,
+    /// Stores the buckets of unordered tries as chains of blocks of doubling size, starting at
+    /// `initial_capacity`, rather than as vectors. Blocks are never reallocated, so items are
+    /// not copied as buckets grow, only once when a bucket is sorted.
+    pub block_buckets: bool,
+    /// Threshold on the bytes of key data past the shared prefix held by a bucket, after which it
+    /// is burst even if it holds fewer than `burst_limit` items.
+    ///
+    /// This keeps the leaf sorts of long keys within a cache-sized budget. Set `burst_limit` to
+    /// [usize::MAX] to burst on bytes alone.
+    pub burst_bytes: Option<usize>,
+    /// Burst limit for each level of the trie, replacing `burst_limit`. Called with the level
+    /// of a node, that is the length of the prefix its keys share.
+    ///
+    /// Shallow nodes split keys into many small buckets and gain the most from bursting early,
+    /// while deep nodes often hold keys with long shared prefixes and gain little from it.
+    pub burst_schedule: Option<fn(usize) -> usize>,
+}
+
+impl BurstConfig {
+    /// Config with the given tuning, and every other option left off.
+    ///
+    /// # Examples
+    /// ```
+    /// use burstsort::BurstConfig;
+    ///
+    /// const CONFIG: BurstConfig = BurstConfig::new(1024, 16, 256, false)
+    ///     .with_max_memory(1 << 20)
+    ///     .with_block_buckets(true);
+    ///
+    /// let mut strings = vec!["pear", "apple", "fig"];
+    /// burstsort::burstsort(&mut strings, &CONFIG);
+    ///
+    /// assert_eq!(vec!["apple", "fig", "pear"], strings);
+    /// ```
+    pub const fn new(burst_limit: usize, initial_capacity: usize, classes: usize,
+                     hint_long: bool) -> Self {
+        Self {
+            burst_limit,
+            initial_capacity,
+            classes,
+            hint_long,
+            max_memory: None,
+            block_buckets: false,
+            burst_bytes: None,
+            burst_schedule: None,
+        }
+    }
+
+    /// Same config with `burst_limit` set.
+    pub const fn with_burst_limit(self, burst_limit: usize) -> Self {
+        Self { burst_limit, ..self }
+    }
+
+    /// Same config with `initial_capacity` set.
+    pub const fn with_initial_capacity(self, initial_capacity: usize) -> Self {
+        Self { initial_capacity, ..self }
+    }
+
+    /// Same config with `classes` set.
+    pub const fn with_classes(self, classes: usize) -> Self {
+        Self { classes, ..self }
+    }
+
+    /// Same config with `hint_long` set.
+    pub const fn with_hint_long(self, hint_long: bool) -> Self {
+        Self { hint_long, ..self }
+    }
+
+    /// Same config with `max_memory` set.
+    pub const fn with_max_memory(self, max_memory: usize) -> Self {
+        Self { max_memory: Some(max_memory), ..self }
+    }
+
+    /// Same config with `block_buckets` set.
+    pub const fn with_block_buckets(self, block_buckets: bool) -> Self {
+        Self { block_buckets, ..self }
+    }
+
+    /// Same config with `burst_bytes` set.
+    pub const fn with_burst_bytes(self, burst_bytes: usize) -> Self {
+        Self { burst_bytes: Some(burst_bytes), ..self }
+    }
+
+    /// Same config with `burst_schedule` set.
+    pub const fn with_burst_schedule(self, burst_schedule: fn(usize) -> usize) -> Self {
+        Self { burst_schedule: Some(burst_schedule), ..self }
+    }
+
+    /// Burst limit for nodes at the given level.
+    ///
+    /// # Examples
+    /// ```
+    /// use burstsort::UTF8_CONFIG;
+    ///
+    /// let config = UTF8_CONFIG.with_burst_limit(64);
+    /// assert_eq!(64, config.burst_limit_at(3));
+    ///
+    /// // burst early near the root, and late from the third level on
+    /// let config = config.with_burst_schedule(|level| [16, 256, 4096][level.min(2)]);
+    /// assert_eq!([16, 256, 4096, 4096], [0, 1, 2, 10].map(|level| config.burst_limit_at(level)));
+    /// ```
+    pub fn burst_limit_at(&self, level: usize) -> usize {
+        self.burst_schedule.map_or(self.burst_limit, |schedule| schedule(level))
+    }
+}
+
+/// Source of the radix keys for the items stored in a trie.
+///
+/// This lets the trie hold something other than the keys themselves, such as indices into a
+/// borrowed slice.
+pub trait KeySource<T, I> {
+    /// Returns the key of the provided item.
+    fn key<'a>(&'a self, item: &'a T) -> &'a [I];
+}
+
+/// Key source for items which are their own keys.
+#[derive(Clone, Copy, Default)]
+pub struct ItemKeys;
+
+impl<T, I> KeySource<T, I> for ItemKeys
+    where T: AsRef<[I]>
+{
+    fn key<'a>(&'a self, item: &'a T) -> &'a [I] {
+        item.as_ref()
+    }
+}
+
+/// Key source for key-value pairs, which are keyed by their first element.
+#[derive(Clone, Copy, Default)]
+pub struct EntryKeys;
+
+impl<K, V, I> KeySource<(K, V), I> for EntryKeys
+    where K: AsRef<[I]>
+{
+    fn key<'a>(&'a self, item: &'a (K, V)) -> &'a [I] {
+        item.0.as_ref()
+    }
+}
+
+/// Key source for indices into a borrowed slice of keys.
+pub struct SliceKeys<'a, D> {
+    data: &'a [D],
+}
+
+impl<'a, D> SliceKeys<'a, D> {
+    pub fn new(data: &'a [D]) -> Self {
+        Self { data }
+    }
+}
+
+impl<'a, D> Clone for SliceKeys<'a, D> {
+    fn clone(&self) -> Self {
+        Self { data: self.data }
+    }
+}
+
+impl<'a, D, I> KeySource<usize, I> for SliceKeys<'a, D>
+    where D: AsRef<[I]>
+{
+    fn key<'b>(&'b self, item: &'b usize) -> &'b [I] {
+        self.data[*item].as_ref()
+    }
 }
 
-#[derive(Clone)]
-pub struct TrieNode<C, T, I> {
+/// Key source for spans of a single borrowed buffer.
+pub struct SpanKeys<'a, I> {
+    buffer: &'a [I],
+}
+
+impl<'a, I> SpanKeys<'a, I> {
+    pub fn new(buffer: &'a [I]) -> Self {
+        Self { buffer }
+    }
+}
+
+impl<'a, I> Clone for SpanKeys<'a, I> {
+    fn clone(&self) -> Self {
+        Self { buffer: self.buffer }
+    }
+}
+
+impl<'a, I> KeySource<Span, I> for SpanKeys<'a, I> {
+    fn key<'b>(&'b self, item: &'b Span) -> &'b [I] {
+        item.get(self.buffer)
+    }
+}
+
+/// Burst trie, made of its root node and the count of the memory allocated by all of its nodes.
+///
+/// The count is kept here rather than in the nodes, and is passed down to the nodes which
+/// allocate or free storage.
+pub struct Trie<C, T, I, K = ItemKeys> {
+    root: TrieNode<C, T, I, K>,
+    memory: Memory,
+}
+
+pub struct TrieNode<C, T, I, K = ItemKeys> {
     level: usize,
     config: C,
+    keys: K,
+    /// Number of items in this subtree, including its matches.
+    len: usize,
+    /// Total length of the keys of the items in this subtree, including its matches.
+    key_len: usize,
     matches: Vec<T>,
-    inner: TrieNodeKind<C, T, I>,
+    /// Key symbols after `level` shared by every item below a burst node, which are skipped
+    /// rather than given a level each. The table is keyed by the symbol after them.
+    skip: Vec<I>,
+    inner: TrieNodeKind<C, T, I, K>,
     _phantom: PhantomData<I>,
 }
 
-#[derive(Clone)]
-pub enum TrieNodeKind<C, T, I> {
+pub enum TrieNodeKind<C, T, I, K = ItemKeys> {
     List(Vec<T>),
-    Burst(Vec<TrieNode<C, T, I>>),
+    /// Bucket made of blocks, used by unordered inserts with [BurstConfig::block_buckets] set.
+    /// Ordered tries only ever hold lists.
+    Blocks(Blocks<T>),
+    Burst(Table<TrieNode<C, T, I, K>>),
+}
+
+impl<C, T, I, K> Trie<C, T, I, K>
+    where C: Borrow<BurstConfig> + Clone,
+          I: Into<usize> + Clone + Ord,
+          K: KeySource<T, I> + Clone
+{
+    pub fn new(config: C, keys: K) -> Self {
+        let root = TrieNode::root(config, keys);
+        let memory = Memory::count(&root);
+
+        Self { root, memory }
+    }
+
+    pub fn insert(&mut self, item: T) {
+        self.root.insert(item, &mut self.memory);
+    }
+
+    pub fn insert_ordered(&mut self, item: T) -> &mut T {
+        self.root.insert_ordered(item, &mut self.memory)
+    }
+
+    /// Approximate bytes allocated by the whole trie. Tries without a memory limit do not keep
+    /// count, and add up the storage of every node instead.
+    pub fn memory_used(&self) -> usize {
+        self.memory.0.unwrap_or_else(|| self.root.heap_size())
+    }
+
+    /// Moves every item of `other` into this trie. The nodes of `other` are taken apart without
+    /// being counted off, so it is counted again once empty.
+    pub fn append(&mut self, other: &mut Self, ordered: bool) {
+        self.root.append(&mut other.root, ordered, &mut self.memory, &mut other.memory);
+        other.memory = Memory::count(&other.root);
+    }
+
+    pub fn append_with<F: FnMut(&mut T, T)>(&mut self, other: &mut Self, combine: &mut F) {
+        self.root.append_with(&mut other.root, combine, &mut self.memory, &mut other.memory);
+        other.memory = Memory::count(&other.root);
+    }
+
+    pub fn set_op(&mut self, other: &mut Self, op: SetOp, target: &mut Vec<T>) {
+        self.root.set_op(&mut other.root, op, target, &mut self.memory, &mut other.memory);
+        other.memory = Memory::count(&other.root);
+    }
+
+    pub fn merge(&mut self, target: &mut Vec<T>) {
+        self.merge_into(&mut Append::new(target));
+    }
+
+    /// Sorts the contents of the trie and drains them into the sink, in order.
+    pub fn merge_into<S: MergeSink<T>>(&mut self, sink: &mut S) {
+        self.root.drain_into(sink, &mut 0, false, &mut self.memory);
+    }
+
+    pub fn merge_ordered(&mut self, target: &mut Vec<T>) {
+        self.root.merge_ordered(target, &mut self.memory);
+    }
+
+    pub fn iter(&self) -> Iter<'_, C, T, I, K> {
+        self.root.iter()
+    }
+
+    pub fn iter_prefix(&self, prefix: &[I]) -> Iter<'_, C, T, I, K> {
+        self.root.iter_prefix(prefix)
+    }
+
+    pub fn count_prefix(&self, prefix: &[I]) -> usize {
+        self.root.count_prefix(prefix)
+    }
+
+    pub fn count(&self) -> usize {
+        self.root.count()
+    }
+
+    pub fn get(&self, key: &[I]) -> Option<&T> {
+        self.root.get(key)
+    }
+
+    pub fn get_mut(&mut self, key: &[I]) -> Option<&mut T> {
+        self.root.get_mut(key)
+    }
+
+    pub fn remove(&mut self, key: &[I]) -> Option<T> {
+        self.root.remove(key, &mut self.memory)
+    }
+
+    pub fn retain<F: FnMut(&mut T) -> bool>(&mut self, f: &mut F) {
+        self.root.retain(f, &mut self.memory);
+    }
+
+    pub fn drain_range(&mut self, lower: Bound<Vec<I>>, upper: Bound<Vec<I>>,
+                       target: &mut Vec<T>) {
+        self.root.drain_range(lower, upper, target, &mut self.memory);
+    }
+
+    pub fn burst_above(&mut self, level: usize) {
+        self.root.burst_above(level, &mut self.memory);
+    }
+
+    pub fn for_each_bucket<F: FnMut(usize, &[T])>(&mut self, f: &mut F) {
+        self.root.for_each_bucket(f, &mut self.memory);
+    }
+
+    pub fn take_buckets<F>(&mut self, take: &mut F, target: &mut Vec<(usize, Vec<T>)>)
+        where F: FnMut(usize, &[T]) -> bool
+    {
+        self.root.take_buckets(take, target, &mut self.memory);
+    }
+
+    /// Replaces the trie with an empty one, freeing all of its storage.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.root.config.clone(), self.root.keys.clone());
+    }
+
+    pub fn longest_prefix_match(&self, key: &[I]) -> Option<&T> {
+        self.root.longest_prefix_match(key)
+    }
+
+    pub fn range(&self, lower: Bound<Vec<I>>, upper: Bound<Vec<I>>) -> Range<'_, C, T, I, K> {
+        self.root.range(lower, upper)
+    }
+
+    pub fn into_sorted_iter(self) -> IntoSortedIter<C, T, I, K> {
+        self.root.into_sorted_iter()
+    }
+
+    pub fn merge_top_k(&mut self, target: &mut Vec<T>, k: usize) {
+        self.root.merge_top_k(target, k, &mut self.memory);
+    }
 }
 
-impl<C, T, I> TrieNode<C, T, I>
+#[cfg(feature = "parallelization")]
+impl<C, T, I, K> Trie<C, T, I, K>
     where C: Borrow<BurstConfig> + Clone + Send + Sync,
-          T: PartialEq + AsRef<[I]> + Clone + Ord + Send + Sync,
-          I: Into<usize> + Clone + Ord + Send + Sync
+          T: Send + Sync,
+          I: Into<usize> + Clone + Ord + Send + Sync,
+          K: KeySource<T, I> + Clone + Send + Sync
+{
+    pub fn par_merge(&mut self, target: &mut Vec<T>) {
+        self.par_merge_into(&mut Append::new(target));
+    }
+
+    pub fn par_merge_into<S: MergeSink<T>>(&mut self, sink: &mut S) {
+        self.root.par_merge_into(sink, &mut self.memory);
+    }
+
+    pub fn par_merge_top_k(&mut self, target: &mut Vec<T>, k: usize) {
+        self.root.par_merge_top_k(target, k, &mut self.memory);
+    }
+}
+
+impl<C, T, I, K> Clone for Trie<C, T, I, K>
+    where C: Clone,
+          T: Clone,
+          I: Clone,
+          K: Clone
+{
+    /// Copies the trie, which starts with its own memory count, as copied storage is only as
+    /// large as it needs to be.
+    fn clone(&self) -> Self {
+        let root = self.root.clone();
+        let memory = Memory(self.memory.0.map(|_| root.heap_size()));
+
+        Self { root, memory }
+    }
+}
+
+impl<C, T, I, K> TrieNode<C, T, I, K>
+    where C: Borrow<BurstConfig> + Clone,
+          I: Into<usize> + Clone + Ord,
+          K: KeySource<T, I> + Clone
 {
-    pub fn root(config: C) -> Self {
+    fn root(config: C, keys: K) -> Self {
+        let cap = config.borrow().initial_capacity;
+
         Self {
             level: 0,
-            matches: Vec::with_capacity(config.borrow().initial_capacity),
-            inner: TrieNodeKind::List(Vec::with_capacity(config.borrow().initial_capacity)),
+            len: 0,
+            key_len: 0,
+            matches: Vec::with_capacity(cap),
+            skip: Vec::new(),
+            inner: TrieNodeKind::List(Vec::with_capacity(cap)),
             config,
-            _phantom: Default::default(),
+            keys,
+            _phantom: PhantomData,
         }
     }
 
-    pub fn insert(&mut self, item: T) {
-        let cap = self.config.borrow().initial_capacity;
+    fn child(level: usize, config: &C, keys: &K) -> Self {
+        Self {
+            level,
+            config: config.clone(),
+            keys: keys.clone(),
+            len: 0,
+            key_len: 0,
+            matches: Vec::new(),
+            skip: Vec::new(),
+            inner: TrieNodeKind::List(Vec::new()),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// An empty node to stand in for this one while it is moved out of the trie.
+    fn placeholder(&self) -> Self {
+        Self::child(self.level, &self.config, &self.keys)
+    }
+
+    /// Walks the subtree depth first without recursing, as long shared prefixes can make the trie
+    /// very deep.
+    ///
+    /// `enter` is called on each node before its children, and returns the children to visit by
+    /// their index in the table, each with the state to enter it with. `leave` is called on each
+    /// node after its children, which are back in place by then. Nodes are moved out of the trie
+    /// while they are being walked, so that a node and its parent can both be changed. Both are
+    /// passed the memory count.
+    fn walk<S, E, L>(&mut self, memory: &mut Memory, state: S, mut enter: E, mut leave: L)
+        where E: FnMut(&mut Self, &mut Memory, S) -> Vec<(usize, S)>,
+              L: FnMut(&mut Self, &mut Memory)
+    {
+        let placeholder = self.placeholder();
+        let mut root = mem::replace(self, placeholder);
+        let children = enter(&mut root, memory, state).into_iter();
+
+        // nodes being walked, each with the children left to visit and the index of the last one
+        let mut stack = vec![(root, children, 0)];
 
-        if let Some(radix) = item.as_ref().get(self.level).cloned() {
-            let radix = radix.into();
+        while let Some((node, children, index)) = stack.last_mut() {
+            if let Some((next, state)) = children.next() {
+                *index = next;
+                let placeholder = node.placeholder();
 
-            match &mut self.inner {
+                let mut child = match &mut node.inner {
+                    TrieNodeKind::Burst(table) => {
+                        mem::replace(&mut table.nodes_mut()[next], placeholder)
+                    }
+                    _ => unreachable!(),
+                };
+
+                let children = enter(&mut child, memory, state).into_iter();
+                stack.push((child, children, 0));
+            } else {
+                let (mut node, _, _) = stack.pop().unwrap();
+                leave(&mut node, memory);
+
+                match stack.last_mut() {
+                    Some((parent, _, index)) => match &mut parent.inner {
+                        TrieNodeKind::Burst(table) => table.nodes_mut()[*index] = node,
+                        _ => unreachable!(),
+                    },
+                    None => *self = node,
+                }
+            }
+        }
+    }
+
+    fn insert(&mut self, item: T, memory: &mut Memory) {
+        let mut node = self;
+
+        // walk down without recursing, as long shared prefixes can make the trie very deep
+        while let Some(radix) = node.descend(&item, false, memory) {
+            node = node.child_mut(radix, memory);
+        }
+
+        let cap = node.initial_capacity(memory);
+
+        if node.keys.key(&item).len() > node.level {
+            // "fresh" list nodes switch over to blocks
+            if let TrieNodeKind::List(list) = &node.inner {
+                if list.is_empty() && node.config.borrow().block_buckets {
+                    memory.resize::<T>(list.capacity(), 0);
+                    node.inner = TrieNodeKind::Blocks(Blocks::default());
+                }
+            }
+
+            let len = match &mut node.inner {
                 TrieNodeKind::List(list) => {
+                    let before = list.capacity();
+
                     // pre-allocate if this is a "fresh" list node
                     if cap > 0 && list.is_empty() {
                         list.reserve(cap);
                     }
 
                     list.push(item);
-
-                    if list.len() > self.config.borrow().burst_limit {
-                        // burst
-                        let mut table = vec![
-                            Self {
-                                level: self.level + 1,
-                                config: self.config.clone(),
-                                matches: Vec::new(),
-                                inner: TrieNodeKind::List(Vec::new()),
-                                _phantom: PhantomData::default(),
-                            };
-                            self.config.borrow().classes];
-
-                        for x in list.drain(..) {
-                            let radix = x.as_ref()[self.level].clone().into();
-                            table[radix].insert(x);
-                        }
-
-                        self.inner = TrieNodeKind::Burst(table)
-                    }
+                    memory.resize::<T>(before, list.capacity());
+                    list.len()
                 }
-                TrieNodeKind::Burst(table) => {
-                    table[radix].insert(item)
+                TrieNodeKind::Blocks(blocks) => {
+                    let before = blocks.capacity();
+
+                    blocks.push(item, cap);
+                    memory.resize::<T>(before, blocks.capacity());
+                    blocks.len()
                 }
+                TrieNodeKind::Burst(_) => unreachable!(),
+            };
+
+            if node.over_limit(len) {
+                node.burst(false, memory);
             }
         } else {
-            // pre-allocate if this is a "fresh" matches list
-            if cap > 0 && self.matches.is_empty() {
-                self.matches.reserve(cap);
-            }
-
-            self.matches.push(item)
+            node.push_match(item, cap, memory);
         }
     }
 
-    pub fn merge(&mut self, target: &mut Vec<T>) {
-        // append exact matches for node first
-        target.append(&mut self.matches);
+    /// Inserts the item while keeping every bucket sorted, so the trie can be read in order.
+    ///
+    /// Returns a reference to the inserted item.
+    fn insert_ordered(&mut self, item: T, memory: &mut Memory) -> &mut T {
+        let mut node = self;
 
-        match &mut self.inner {
-            TrieNodeKind::List(list) => {
-                // now sort internal collection and append
+        while let Some(radix) = node.descend(&item, true, memory) {
+            node = node.child_mut(radix, memory);
+        }
 
-                // if arrays may be long, best to only sort the remaining elements
-                if self.config.borrow().hint_long {
-                    let level = self.level;
+        let cap = node.initial_capacity(memory);
 
-                    list.sort_unstable_by(|lhs, rhs| {
-                        let lhs_remaining = &lhs.as_ref()[level..];
-                        let rhs_remaining = &rhs.as_ref()[level..];
-                        lhs_remaining.cmp(rhs_remaining)
-                    });
-                } else {
-                    list.sort_unstable();
+        if node.keys.key(&item).len() <= node.level {
+            node.push_match(item, cap, memory);
+            return node.matches.last_mut().unwrap();
+        }
+
+        match &mut node.inner {
+            TrieNodeKind::List(list) => {
+                let before = list.capacity();
+
+                // pre-allocate if this is a "fresh" list node
+                if cap > 0 && list.is_empty() {
+                    list.reserve(cap);
                 }
 
-                target.append(list);
+                let (keys, level) = (&node.keys, node.level);
+                let key = &keys.key(&item)[level..];
+
+                // insert after any equal items, so that duplicates stay in insertion order
+                let index = list.partition_point(|x| &keys.key(x)[level..] <= key);
+                list.insert(index, item);
+                memory.resize::<T>(before, list.capacity());
+
+                &mut list[index]
             }
-            TrieNodeKind::Burst(table) => {
-                // sequentially merge each table entry
-                for x in table.iter_mut() {
-                    x.merge(target)
-                }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Counts the item towards this subtree, and finds the radix of the child it belongs in, if
+    /// the node has burst. Otherwise the item belongs in this node.
+    ///
+    /// Ordered tries burst ahead of time, so that the item only has to be placed once. Skipped
+    /// paths which the item leaves or ends along are split first.
+    fn descend(&mut self, item: &T, ordered: bool, memory: &mut Memory) -> Option<usize> {
+        self.len += 1;
+        self.key_len += self.keys.key(item).len();
+
+        if self.keys.key(item).len() <= self.level {
+            return None;
+        }
+
+        if let TrieNodeKind::List(list) = &self.inner {
+            if ordered && self.over_limit(list.len() + 1) {
+                self.burst_sharing(true, Some(item), memory);
             }
         }
+
+        if !matches!(self.inner, TrieNodeKind::Burst(_)) {
+            return None;
+        }
+
+        if !self.skip.is_empty() {
+            self.split_skipped(item, memory);
+        }
+
+        Some(self.keys.key(item)[self.level + self.skip.len()].clone().into())
     }
 
-    #[cfg(feature = "parallelization")]
-    pub fn par_merge(&mut self, target: &mut Vec<T>) {
-        rayon::scope(|s| {
-            self.par_sort(s);
-        });
+    /// Adds an item whose key ends at this level.
+    fn push_match(&mut self, item: T, cap: usize, memory: &mut Memory) {
+        let before = self.matches.capacity();
 
-        self.merge_sorted(target);
+        // pre-allocate if this is a "fresh" matches list
+        if cap > 0 && self.matches.is_empty() {
+            self.matches.reserve(cap);
+        }
+
+        self.matches.push(item);
+        memory.resize::<T>(before, self.matches.capacity());
     }
 
-    #[cfg(feature = "parallelization")]
-    fn merge_sorted(&mut self, target: &mut Vec<T>) {
-        target.append(&mut self.matches);
+    /// Replaces the bucket of this node with a table of child nodes, keyed by the next radix.
+    ///
+    /// The symbols shared by every item are skipped, so that a long common prefix does not burst
+    /// into a chain of nodes with a single child each.
+    fn burst(&mut self, ordered: bool, memory: &mut Memory) {
+        self.burst_sharing(ordered, None, memory);
+    }
 
-        match &mut self.inner {
-            TrieNodeKind::List(list) => {
-                target.append(list);
+    /// Bursts the node like [TrieNode::burst], ahead of placing an item below it. Only the
+    /// symbols which the item shares as well are skipped, so that it does not split the node again.
+    fn burst_sharing(&mut self, ordered: bool, mut item: Option<&T>, memory: &mut Memory) {
+        // children over the limits are burst in turn, without recursing
+        let mut pending = vec![self];
+
+        while let Some(node) = pending.pop() {
+            let (capacity, bucket) = match &mut node.inner {
+                TrieNodeKind::List(list) => (list.capacity(), Blocks::from(mem::take(list))),
+                TrieNodeKind::Blocks(blocks) => (blocks.capacity(), mem::take(blocks)),
+                TrieNodeKind::Burst(_) => continue,
+            };
+
+            // the item is only placed below the node burst first
+            node.skip = node.shared_symbols(&bucket, item.take());
+            memory.resize::<T>(capacity, 0);
+            node.inner = TrieNodeKind::Burst(node.table(memory));
+            node.distribute(bucket, ordered, memory);
+
+            if let TrieNodeKind::Burst(table) = &mut node.inner {
+                let over = table.nodes_mut().iter_mut().filter(|x| x.over_limit(x.inner_len()));
+                pending.extend(over);
             }
-            TrieNodeKind::Burst(table) => {
-                for x in table.iter_mut() {
-                    x.merge_sorted(target)
-                }
+        }
+    }
+
+    /// Moves the items of a burst bucket into the fresh children of this node in a single
+    /// counting pass, as in MSD radix sort. Each key is looked up once, each child's storage is
+    /// sized up front, and items whose keys end at a child go straight to its matches.
+    ///
+    /// Blocks are drained in order, so ordered buckets stay sorted.
+    fn distribute(&mut self, bucket: Blocks<T>, ordered: bool, memory: &mut Memory) {
+        let level = self.level + self.skip.len();
+        let cap = self.initial_capacity(memory);
+        let block_buckets = !ordered && self.config.borrow().block_buckets;
+
+        let keys = &self.keys;
+        let radixes: Vec<(usize, usize)> = bucket.iter()
+            .map(|x| (keys.key(x)[level].clone().into(), keys.key(x).len()))
+            .collect();
+
+        // matches and bucket items of each child
+        let mut counts = vec![(0, 0); self.config.borrow().classes];
+
+        for &(radix, len) in &radixes {
+            if len == level + 1 {
+                counts[radix].0 += 1;
+            } else {
+                counts[radix].1 += 1;
+            }
+        }
+
+        for (radix, &(matches, items)) in counts.iter().enumerate() {
+            if matches + items == 0 {
+                continue;
+            }
+
+            let child = self.child_mut(radix, memory);
+            let list = Vec::with_capacity(if items > 0 { items.max(cap) } else { 0 });
+
+            if matches > 0 {
+                child.matches.reserve(matches.max(cap));
+            }
+
+            memory.resize::<T>(0, child.matches.capacity() + list.capacity());
+            child.inner = match block_buckets && items > 0 {
+                true => TrieNodeKind::Blocks(Blocks::from(list)),
+                false => TrieNodeKind::List(list),
+            };
+        }
+
+        for (x, (radix, len)) in bucket.into_iter().zip(radixes) {
+            let child = self.child_mut(radix, memory);
+            child.len += 1;
+            child.key_len += len;
+
+            if len == level + 1 {
+                child.matches.push(x);
+                continue;
+            }
+
+            match &mut child.inner {
+                TrieNodeKind::List(list) => list.push(x),
+                TrieNodeKind::Blocks(blocks) => blocks.push(x, cap),
+                TrieNodeKind::Burst(_) => unreachable!(),
             }
         }
     }
 
-    #[cfg(feature = "parallelization")]
-    fn par_sort<'scope>(&'scope mut self, scope: &rayon::Scope<'scope>) {
-        let long = self.config.borrow().hint_long;
-        let level = self.level;
-        match &mut self.inner {
-            TrieNodeKind::List(list) => {
-                if !list.is_empty() {
-                    scope.spawn(move |_| {
-                        if long {
-                            list.par_sort_unstable_by(|lhs, rhs| {
-                                let lhs_remaining = &lhs.as_ref()[level..];
-                                let rhs_remaining = &rhs.as_ref()[level..];
-                                lhs_remaining.cmp(rhs_remaining)
-                            });
-                        } else {
-                            list.par_sort_unstable();
-                        }
-                    })
-                }
+    /// Creates an empty table for the children of this node, which are keyed by the symbol after
+    /// its skipped ones.
+    fn table(&self, memory: &mut Memory) -> Table<Self> {
+        let level = self.level + self.skip.len();
+        let (config, keys) = (&self.config, &self.keys);
+
+        let table = if self.is_lean(memory) {
+            Table::sparse()
+        } else {
+            Table::dense((0..config.borrow().classes)
+                .map(|_| Self::child(level + 1, config, keys))
+                .collect())
+        };
+
+        memory.add(table.heap_size());
+
+        table
+    }
+
+    /// Finds the key symbols after this level which every item of the bucket shares, along with
+    /// the extra item if any, leaving each item at least one more symbol to be keyed by.
+    fn shared_symbols(&self, bucket: &Blocks<T>, extra: Option<&T>) -> Vec<I> {
+        let (keys, level) = (&self.keys, self.level);
+        let mut items = extra.into_iter().chain(bucket.iter()).map(|x| &keys.key(x)[level..]);
+
+        let mut shared = match items.next() {
+            Some(first) => &first[..first.len() - 1],
+            None => return Vec::new(),
+        };
+
+        for key in items {
+            // most keys follow the whole shared path, which is quicker to check as a slice
+            if key.len() > shared.len() && key.starts_with(shared) {
+                continue;
             }
-            TrieNodeKind::Burst(table) => {
-                scope.spawn(move |s| {
-                    for x in table.iter_mut() {
-                        x.par_sort(s);
-                    }
-                });
+
+            let len = shared.iter().zip(key).take_while(|(x, y)| x == y).count();
+            shared = &shared[..len.min(key.len() - 1)];
+
+            if shared.is_empty() {
+                break;
             }
         }
+
+        shared.to_vec()
+    }
+
+    /// Splits the skipped path of a burst node where the item leaves it, or where it ends along
+    /// it, so that the item can be placed below the node.
+    fn split_skipped(&mut self, item: &T, memory: &mut Memory) {
+        let key = &self.keys.key(item)[self.level..];
+
+        if key.len() > self.skip.len() && key.starts_with(&self.skip) {
+            return;
+        }
+
+        let shared = key.iter().zip(&self.skip).take_while(|(x, y)| x == y).count();
+
+        if shared < self.skip.len() && shared < key.len() {
+            self.split(shared, memory);
+        } else if shared == key.len() {
+            // the item ends along the path, and becomes a match of the node split off below
+            self.split(shared - 1, memory);
+        }
+    }
+
+    /// Shortens the skipped path of a burst node to its first `at` symbols. The rest of the node
+    /// moves down into a new child, keyed by the symbol at `at`.
+    fn split(&mut self, at: usize, memory: &mut Memory) {
+        let rest = self.skip.split_off(at + 1);
+        let radix = self.skip.pop().unwrap().into();
+
+        let mut moved = Self::child(self.level + at + 1, &self.config, &self.keys);
+        moved.skip = rest;
+        let table = self.table(memory);
+        moved.inner = mem::replace(&mut self.inner, TrieNodeKind::Burst(table));
+        moved.len = moved.inner_len();
+        moved.count_key_len();
+
+        *self.child_mut(radix, memory) = moved;
     }
-}
\ No newline at end of file
+
+    /// Finds the child of a burst node for the radix, creating it if the table is sparse.
+    fn child_mut(&mut self, radix: usize, memory: &mut Memory) -> &mut Self {
+        let level = self.level + self.skip.len();
+        let (config, keys) = (&self.config, &self.keys);
+
+        match &mut self.inner {
+            TrieNodeKind::Burst(table) => {
+                table.slot(radix, memory, || Self::child(level + 1, config, keys))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Finds the radix of the child of a burst node which a key longer than its level belongs
+    /// under. Keys which leave the skipped path of the node, or end along it, belong under none.
+    fn child_radix(&self, key: &[I]) -> Option<usize> {
+        let level = self.level + self.skip.len();
+
+        match key.len() > level && key[self.level..level] == self.skip[..] {
+            true => Some(key[level].clone().into()),
+            false => None,
+        }
+    }
+
+    /// Cuts the skipped paths of two burst nodes back to the symbols both share, so that their
+    /// tables are keyed by the same symbol. Each node is split against the count of its own trie.
+    fn align_skips(&mut self, other: &mut Self, memory: &mut Memory, other_memory: &mut Memory) {
+        let shared = self.skip.iter().zip(&other.skip).take_while(|(x, y)| x == y).count();
+
+        if shared < self.skip.len() {
+            self.split(shared, memory);
+        }
+
+        if shared < other.skip.len() {
+            other.split(shared, other_memory);
+        }
+    }
+
+    /// Whether the bucket of a list node holding `len` items is over the burst limits. A lone item
+    /// is never burst for its size, as that would not split it up.
+    fn over_limit(&self, len: usize) -> bool {
+        let config = self.config.borrow();
+        len > config.burst_limit_at(self.level)
+            || (len > 1 && config.burst_bytes.is_some_and(|max| self.key_bytes() > max))
+    }
+
+    /// Bytes of key data past the level of this node, held by the items of the subtree.
+    fn key_bytes(&self) -> usize {
+        // matches end at this level, so only contribute to the first part
+        (self.key_len - self.level * self.len) * mem::size_of::<I>()
+    }
+
+    /// Recounts the total key length of the subtree, from its matches and its children.
+    fn count_key_len(&mut self) {
+        let (keys, level) = (&self.keys, self.level);
+        let inner: usize = match &self.inner {
+            TrieNodeKind::List(list) => list.iter().map(|x| keys.key(x).len()).sum(),
+            TrieNodeKind::Blocks(blocks) => blocks.iter().map(|x| keys.key(x).len()).sum(),
+            TrieNodeKind::Burst(table) => table.nodes().iter().map(|x| x.key_len).sum(),
+        };
+
+        self.key_len = self.matches.len() * level + inner;
+    }
+
+    /// Moves the items of a block bucket into a single list, in order.
+    fn flatten_blocks(&mut self, memory: &mut Memory) {
+        if let TrieNodeKind::Blocks(blocks) = &mut self.inner {
+            let before = blocks.capacity();
+            let list = mem::take(blocks).into_vec();
+
+            memory.resize::<T>(before, list.capacity());
+            self.inner = TrieNodeKind::List(list);
+        }
+    }
+
+    /// Takes the items of a bucket node, leaving it empty.
+    fn take_bucket(&mut self) -> Blocks<T> {
+        match &mut self.inner {
+            TrieNodeKind::List(list) => Blocks::from(mem::take(list)),
+            TrieNodeKind::Blocks(blocks) => mem::take(blocks),
+            TrieNodeKind::Burst(_) => Blocks::default(),
+        }
+    }
+
+    /// Whether the trie has reached its memory limit, and should allocate as little as possible.
+    fn is_lean(&self, memory: &Memory) -> bool {
+        self.config.borrow().max_memory.is_some_and(|max| memory.reached(max))
+    }
+
+    /// Capacity to pre-allocate for fresh storage vectors.
+    fn initial_capacity(&self, memory: &Memory) -> usize {
+        match self.is_lean(memory) {
+            true => 0,
+            false => self.config.borrow().initial_capacity,
+        }
+    }
+
+    /// Moves every item of another trie with the same config into this one, leaving it empty.
+    ///
+    /// Both tries are walked together node by node: matches are concatenated, lists are merged,
+    /// and a list meeting a table is burst first. Where skipped paths differ, both are split
+    /// where they part, so that their tables line up. Ordered tries keep their buckets sorted,
+    /// with items from `other` placed after equal items from this trie.
+    fn append(&mut self, other: &mut Self, ordered: bool, memory: &mut Memory,
+              other_memory: &mut Memory) {
+        let placeholder = other.placeholder();
+        let other = mem::replace(other, placeholder);
+
+        // the nodes of `other` are carried along the walk, each to the node it is appended to
+        self.walk(memory, other, |node, memory, mut other| {
+            node.append_node(&mut other, ordered, memory, other_memory)
+        }, |_, _| {});
+    }
+
+    /// Appends the matches and bucket of another node to this one. If both have burst, returns
+    /// the children of `other`, each with the index of the child of this node to append it to.
+    fn append_node(&mut self, other: &mut Self, ordered: bool, memory: &mut Memory,
+                   other_memory: &mut Memory) -> Vec<(usize, Self)> {
+        if let TrieNodeKind::Burst(_) = &other.inner {
+            self.burst(ordered, memory);
+            self.align_skips(other, memory, other_memory);
+        }
+
+        self.len += mem::replace(&mut other.len, 0);
+        self.key_len += mem::replace(&mut other.key_len, 0);
+        let before = self.matches.capacity();
+        self.matches.append(&mut other.matches);
+        memory.resize::<T>(before, self.matches.capacity());
+
+        if let TrieNodeKind::Burst(other_table) = &other.inner {
+            let radixes = other_table.radixes().collect();
+            return self.pair_children(radixes, other.release(), memory);
+        }
+
+        let mut bucket = other.take_bucket();
+        let (level, keys) = (self.level, &self.keys);
+
+        let len = match &mut self.inner {
+            TrieNodeKind::Burst(_) => {
+                for x in bucket {
+                    if !self.skip.is_empty() {
+                        self.split_skipped(&x, memory);
+                    }
+
+                    let radix = self.keys.key(&x)[level + self.skip.len()].clone().into();
+
+                    if ordered {
+                        self.child_mut(radix, memory).insert_ordered(x, memory);
+                    } else {
+                        self.child_mut(radix, memory).insert(x, memory);
+                    }
+                }
+
+                return Vec::new();
+            }
+            TrieNodeKind::List(list) => {
+                let before = list.capacity();
+
+                if ordered {
+                    *list = merge_sorted(mem::take(list), bucket.into_vec(), keys, level);
+                } else {
+                    list.extend(bucket);
+                }
+
+                memory.resize::<T>(before, list.capacity());
+                list.len()
+            }
+            TrieNodeKind::Blocks(blocks) => {
+                let before = blocks.capacity();
+
+                // blocks are chained as they are, without copying
+                blocks.append(&mut bucket);
+
+                memory.resize::<T>(before, blocks.capacity());
+                blocks.len()
+            }
+        };
+
+        if self.over_limit(len) {
+            self.burst(ordered, memory);
+        }
+
+        Vec::new()
+    }
+
+    /// Pairs up the children of another burst node with the children of this one with the same
+    /// radixes, creating those that are missing. Returns each child of the other node along with
+    /// the index of its counterpart.
+    fn pair_children(&mut self, radixes: Vec<usize>, children: Vec<Self>, memory: &mut Memory)
+                     -> Vec<(usize, Self)> {
+        for &radix in &radixes {
+            self.child_mut(radix, memory);
+        }
+
+        match &self.inner {
+            TrieNodeKind::Burst(table) => {
+                radixes.into_iter().map(|radix| table.find(radix).unwrap()).zip(children).collect()
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Moves every item of another ordered trie with the same config into this one, leaving it
+    /// empty. Unlike [TrieNode::append], an item whose key is already present is passed to
+    /// `combine` along with the existing item, rather than being kept.
+    ///
+    /// Both tries must hold distinct keys, as built by inserting through [TrieNode::get_mut].
+    fn append_with<F>(&mut self, other: &mut Self, combine: &mut F, memory: &mut Memory,
+                      other_memory: &mut Memory)
+        where F: FnMut(&mut T, T)
+    {
+        let placeholder = other.placeholder();
+        let other = mem::replace(other, placeholder);
+
+        self.walk(memory, other, |node, memory, mut other| {
+            node.append_node_with(&mut other, combine, memory, other_memory)
+        }, |node, memory| {
+            // duplicates are only known once the children are done
+            node.len = node.matches.len() + node.inner_len();
+            node.count_key_len();
+
+            if let TrieNodeKind::List(list) = &node.inner {
+                if node.over_limit(list.len()) {
+                    node.burst(true, memory);
+                }
+            }
+        });
+    }
+
+    /// Combines the matches and bucket of another node into this one. If both have burst, returns
+    /// the children of `other`, each with the index of the child of this node to combine it with.
+    fn append_node_with<F>(&mut self, other: &mut Self, combine: &mut F, memory: &mut Memory,
+                           other_memory: &mut Memory) -> Vec<(usize, Self)>
+        where F: FnMut(&mut T, T)
+    {
+        match (self.matches.last_mut(), other.matches.pop()) {
+            (Some(x), Some(y)) => combine(x, y),
+            (None, Some(y)) => self.push_match(y, 0, memory),
+            _ => {}
+        }
+
+        if let TrieNodeKind::Burst(_) = &other.inner {
+            self.burst(true, memory);
+            self.align_skips(other, memory, other_memory);
+        }
+
+        if let TrieNodeKind::Burst(other_table) = &other.inner {
+            let radixes = other_table.radixes().collect();
+            return self.pair_children(radixes, other.release(), memory);
+        }
+
+        let (level, keys) = (self.level, self.keys.clone());
+        let bucket = other.take_bucket().into_vec();
+
+        match &mut self.inner {
+            TrieNodeKind::Burst(_) => {
+                // the counts of this node are redone once its children are
+                for x in bucket {
+                    if let Some(existing) = self.get_mut(keys.key(&x)) {
+                        combine(existing, x);
+                    } else {
+                        self.insert_ordered(x, memory);
+                    }
+                }
+            }
+            TrieNodeKind::List(list) => {
+                let before = list.capacity();
+                *list = merge_sorted_with(mem::take(list), bucket, &keys, level, combine);
+                memory.resize::<T>(before, list.capacity());
+            }
+            TrieNodeKind::Blocks(_) => unreachable!(),
+        }
+
+        Vec::new()
+    }
+
+    /// Drains two ordered tries with the same config, appending the distinct keys selected by
+    /// the set operation to the target in order.
+    ///
+    /// Where both tries have burst, their children are combined pairwise. Elsewhere, the
+    /// remaining items of both subtrees are drained and combined in a single linear pass.
+    fn set_op(&mut self, other: &mut Self, op: SetOp, target: &mut Vec<T>, memory: &mut Memory,
+              other_memory: &mut Memory) {
+        let placeholder = other.placeholder();
+        let other = mem::replace(other, placeholder);
+
+        self.walk(memory, other, |node, memory, mut other| {
+            node.set_op_node(&mut other, op, target, memory, other_memory)
+        }, |_, _| {});
+        self.clear(memory);
+    }
+
+    /// Applies the set operation to the matches of two nodes. If both have burst, returns the
+    /// children of `other` for every radix found on either side, each with the index of its
+    /// counterpart. Otherwise both subtrees are drained and combined.
+    fn set_op_node(&mut self, other: &mut Self, op: SetOp, target: &mut Vec<T>,
+                   memory: &mut Memory, other_memory: &mut Memory) -> Vec<(usize, Self)> {
+        if op.keep(!self.matches.is_empty(), !other.matches.is_empty()) {
+            target.extend(self.matches.drain(..).chain(other.matches.drain(..)).next());
+        }
+
+        self.matches.clear();
+        other.matches.clear();
+
+        if let (TrieNodeKind::Burst(_), TrieNodeKind::Burst(_)) = (&self.inner, &other.inner) {
+            self.align_skips(other, memory, other_memory);
+        }
+
+        if let (TrieNodeKind::Burst(table), TrieNodeKind::Burst(other_table)) =
+            (&self.inner, &other.inner) {
+            // every radix found on either side, in order
+            let mut radixes: Vec<_> = table.radixes().chain(other_table.radixes()).collect();
+            radixes.sort_unstable();
+            radixes.dedup();
+
+            // nodes missing from `other` are stood in for by empty ones
+            let other_radixes: Vec<_> = other_table.radixes().collect();
+            let empty = Self::child(other.level + 1, &other.config, &other.keys);
+            let mut children = other_radixes.into_iter().zip(other.release()).peekable();
+
+            let children = radixes.iter().map(|&radix| {
+                match children.next_if(|(x, _)| *x == radix) {
+                    Some((_, x)) => x,
+                    None => empty.placeholder(),
+                }
+            }).collect();
+
+            self.pair_children(radixes, children, memory)
+        } else {
+            let mut left = Vec::with_capacity(self.len);
+            let mut right = Vec::with_capacity(other.len);
+
+            self.merge_ordered(&mut left, memory);
+            other.merge_ordered(&mut right, other_memory);
+
+            merge_sets(left, right, &self.keys, self.level, op, target);
+            Vec::new()
+        }
+    }
+
+    /// Drains the contents of a trie built with [TrieNode::insert_ordered] into the target.
+    fn merge_ordered(&mut self, target: &mut Vec<T>, memory: &mut Memory) {
+        self.drain_into(&mut Append::new(target), &mut 0, true, memory);
+    }
+
+    /// Iterates over the items of a trie built with [TrieNode::insert_ordered], in order.
+    fn iter(&self) -> Iter<'_, C, T, I, K> {
+        Iter {
+            current: [].iter(),
+            pending: vec![IterPending::Node(self)],
+        }
+    }
+
+    /// Iterates over the items of an ordered trie with keys starting with the prefix, in order.
+    fn iter_prefix(&self, prefix: &[I]) -> Iter<'_, C, T, I, K> {
+        match self.find_prefix(prefix) {
+            PrefixMatch::Subtree(node) => node.iter(),
+            PrefixMatch::Children(table) => Iter {
+                current: [].iter(),
+                pending: table.nodes().iter().rev().map(IterPending::Node).collect(),
+            },
+            PrefixMatch::Bucket(list) => Iter {
+                current: list.iter(),
+                pending: Vec::new(),
+            },
+        }
+    }
+
+    /// Counts the items of an ordered trie with keys starting with the prefix.
+    fn count_prefix(&self, prefix: &[I]) -> usize {
+        match self.find_prefix(prefix) {
+            PrefixMatch::Subtree(node) => node.count(),
+            PrefixMatch::Children(table) => table.nodes().iter().map(|x| x.count()).sum(),
+            PrefixMatch::Bucket(list) => list.len(),
+        }
+    }
+
+    /// Counts the items in this subtree.
+    fn count(&self) -> usize {
+        self.len
+    }
+
+    /// Descends the trie along the prefix, to the part of it holding keys with that prefix.
+    fn find_prefix(&self, prefix: &[I]) -> PrefixMatch<'_, C, T, I, K> {
+        let mut node = self;
+
+        loop {
+            // every key below this node starts with the prefix
+            if prefix.len() <= node.level {
+                return PrefixMatch::Subtree(node);
+            }
+
+            match &node.inner {
+                TrieNodeKind::List(list) => {
+                    let (keys, level) = (&node.keys, node.level);
+                    let prefix = &prefix[level..];
+
+                    // matching keys are contiguous in the sorted bucket
+                    let start = list.partition_point(|x| &keys.key(x)[level..] < prefix);
+                    let len = list[start..]
+                        .partition_point(|x| keys.key(x)[level..].starts_with(prefix));
+
+                    return PrefixMatch::Bucket(&list[start..start + len]);
+                }
+                TrieNodeKind::Blocks(_) => unreachable!(),
+                TrieNodeKind::Burst(table) => {
+                    let rest = &prefix[node.level..];
+
+                    // a prefix ending along the skipped path is shared by every key below the
+                    // node, other than its matches
+                    if rest.len() <= node.skip.len() {
+                        return match node.skip.starts_with(rest) {
+                            true => PrefixMatch::Children(table),
+                            false => PrefixMatch::Bucket(&[]),
+                        };
+                    }
+
+                    match node.child_radix(prefix).and_then(|radix| table.get(radix)) {
+                        Some(child) => node = child,
+                        None => return PrefixMatch::Bucket(&[]),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Finds the first item with the given key in an ordered trie.
+    fn get(&self, key: &[I]) -> Option<&T> {
+        let mut node = self;
+
+        loop {
+            if key.len() <= node.level {
+                return node.matches.first();
+            }
+
+            match &node.inner {
+                TrieNodeKind::List(list) => {
+                    return search(list, &node.keys, node.level, key).map(|i| &list[i]);
+                }
+                TrieNodeKind::Blocks(_) => unreachable!(),
+                TrieNodeKind::Burst(table) => node = table.get(node.child_radix(key)?)?,
+            }
+        }
+    }
+
+    /// Finds the first item with the given key in an ordered trie.
+    fn get_mut(&mut self, key: &[I]) -> Option<&mut T> {
+        let mut node = self;
+
+        loop {
+            if key.len() <= node.level {
+                return node.matches.first_mut();
+            }
+
+            let radix = node.child_radix(key);
+
+            match &mut node.inner {
+                TrieNodeKind::List(list) => {
+                    let index = search(list, &node.keys, node.level, key)?;
+                    return Some(&mut list[index]);
+                }
+                TrieNodeKind::Blocks(_) => unreachable!(),
+                TrieNodeKind::Burst(table) => node = table.get_mut(radix?)?,
+            }
+        }
+    }
+
+    /// Removes the first item with the given key from an ordered trie.
+    fn remove(&mut self, key: &[I], memory: &mut Memory) -> Option<T> {
+        // the item is found first, so that the nodes can be updated on the way down to it
+        self.get(key)?;
+        let mut node = self;
+
+        loop {
+            // the removed item has exactly the given key
+            node.len -= 1;
+            node.key_len -= key.len();
+
+            if key.len() <= node.level {
+                let removed = node.matches.remove(0);
+                node.shrink(memory);
+                return Some(removed);
+            }
+
+            // a node collapsing here still holds the item in its new list
+            node.shrink(memory);
+            let radix = node.child_radix(key);
+
+            match &mut node.inner {
+                TrieNodeKind::List(list) => {
+                    let index = search(list, &node.keys, node.level, key).unwrap();
+                    return Some(list.remove(index));
+                }
+                TrieNodeKind::Blocks(_) => unreachable!(),
+                TrieNodeKind::Burst(table) => node = table.get_mut(radix.unwrap()).unwrap(),
+            }
+        }
+    }
+
+    /// Keeps only the items of an ordered trie for which the predicate returns true.
+    ///
+    /// The predicate must not change the keys of the items.
+    fn retain<F: FnMut(&mut T) -> bool>(&mut self, f: &mut F, memory: &mut Memory) {
+        self.walk(memory, (), |node, _, ()| {
+            node.matches.retain_mut(|x| f(x));
+
+            match &mut node.inner {
+                TrieNodeKind::List(list) => {
+                    list.retain_mut(|x| f(x));
+                    Vec::new()
+                }
+                TrieNodeKind::Blocks(_) => unreachable!(),
+                TrieNodeKind::Burst(table) => (0..table.nodes().len()).map(|x| (x, ())).collect(),
+            }
+        }, |node, memory| {
+            node.len = node.matches.len() + node.inner_len();
+            node.count_key_len();
+            node.shrink(memory);
+        });
+    }
+
+    /// Removes the items of an ordered trie with keys inside the bounds, appending them to the
+    /// target in order.
+    fn drain_range(&mut self, lower: Bound<Vec<I>>, upper: Bound<Vec<I>>, target: &mut Vec<T>,
+                   memory: &mut Memory) {
+        let bounds = KeyBounds::new(lower, upper);
+
+        // each node is entered with whether it is tight against the lower and upper bounds
+        self.walk(memory, bounds.is_bounded(), |node, _, (lower_tight, upper_tight)| {
+            let range = bounds.clamp(&node.matches, &node.keys, lower_tight, upper_tight);
+            target.extend(node.matches.drain(range));
+
+            match &mut node.inner {
+                TrieNodeKind::List(list) => {
+                    let range = bounds.clamp(list, &node.keys, lower_tight, upper_tight);
+                    target.extend(list.drain(range));
+                    Vec::new()
+                }
+                TrieNodeKind::Blocks(_) => unreachable!(),
+                TrieNodeKind::Burst(table) => {
+                    bounds.children(node.level, &node.skip, table, lower_tight, upper_tight)
+                        .map(|(index, lower, upper)| (index, (lower, upper)))
+                        .collect()
+                }
+            }
+        }, |node, memory| {
+            node.len = node.matches.len() + node.inner_len();
+            node.count_key_len();
+            node.shrink(memory);
+        });
+    }
+
+    /// Collapses a burst node of an ordered trie back into a list node, once its table holds
+    /// so few items that it is mostly empty.
+    fn shrink(&mut self, memory: &mut Memory) {
+        let remaining = self.len - self.matches.len();
+        let (burst_limit, burst_bytes) = {
+            let config = self.config.borrow();
+            (config.burst_limit_at(self.level), config.burst_bytes)
+        };
+
+        let is_burst = matches!(self.inner, TrieNodeKind::Burst(_));
+
+        if !is_burst || remaining * UNBURST_DIVISOR >= burst_limit {
+            return;
+        }
+
+        // with a byte limit, the list must also hold few enough bytes not to burst again soon
+        if burst_bytes.is_some_and(|max| self.key_bytes() * UNBURST_DIVISOR >= max) {
+            return;
+        }
+
+        // the children are dropped along with the table, storage and all
+        let before = memory.is_counted().then(|| self.heap_size());
+
+        if let TrieNodeKind::Burst(table) = &mut self.inner {
+            let mut list = Vec::with_capacity(remaining);
+
+            // children are in order and their buckets are sorted, so the list is sorted
+            for x in table.nodes_mut() {
+                x.drain_into(&mut Append::new(&mut list), &mut 0, true, memory);
+            }
+
+            self.inner = TrieNodeKind::List(list);
+            self.skip = Vec::new();
+        }
+
+        if let Some(before) = before {
+            memory.resize::<u8>(before, self.heap_size());
+        }
+    }
+
+    /// Bursts every bucket of an unordered trie above `level`, however few items it holds, so
+    /// that all remaining buckets are at `level` or deeper.
+    fn burst_above(&mut self, level: usize, memory: &mut Memory) {
+        let mut pending = vec![self];
+
+        while let Some(node) = pending.pop() {
+            let len = match &node.inner {
+                TrieNodeKind::List(list) => list.len(),
+                TrieNodeKind::Blocks(blocks) => blocks.len(),
+                TrieNodeKind::Burst(_) => 0,
+            };
+
+            if node.level < level && len > 0 {
+                node.burst(false, memory);
+            }
+
+            if let TrieNodeKind::Burst(table) = &mut node.inner {
+                pending.extend(table.nodes_mut());
+            }
+        }
+    }
+
+    /// Calls `f` with the level and contents of every bucket in the trie. Block buckets are
+    /// flattened into lists first. The matches of each node are passed the same way, before its
+    /// bucket or children, and are told apart by their keys ending at the level.
+    fn for_each_bucket<F: FnMut(usize, &[T])>(&mut self, f: &mut F, memory: &mut Memory) {
+        let mut pending = vec![self];
+
+        while let Some(node) = pending.pop() {
+            node.flatten_blocks(memory);
+
+            if !node.matches.is_empty() {
+                f(node.level, &node.matches);
+            }
+
+            match &mut node.inner {
+                TrieNodeKind::List(list) => f(node.level, list),
+                TrieNodeKind::Blocks(_) => unreachable!(),
+                TrieNodeKind::Burst(table) => pending.extend(table.nodes_mut().iter_mut().rev()),
+            }
+        }
+    }
+
+    /// Moves the contents of every bucket selected by `take` into the target, along with the
+    /// level of the node. Matches are offered as in [TrieNode::for_each_bucket]. The emptied
+    /// nodes stay in the trie.
+    fn take_buckets<F>(&mut self, take: &mut F, target: &mut Vec<(usize, Vec<T>)>,
+                       memory: &mut Memory)
+        where F: FnMut(usize, &[T]) -> bool
+    {
+        self.walk(memory, (), |node, memory, ()| {
+            node.flatten_blocks(memory);
+
+            if !node.matches.is_empty() && take(node.level, &node.matches) {
+                let matches = mem::take(&mut node.matches);
+
+                // matches have keys of exactly the length of the level
+                node.len -= matches.len();
+                node.key_len -= matches.len() * node.level;
+                memory.resize::<T>(matches.capacity(), 0);
+                target.push((node.level, matches));
+            }
+
+            match &mut node.inner {
+                TrieNodeKind::List(list) => {
+                    if take(node.level, list) {
+                        node.len -= list.len();
+                        memory.resize::<T>(list.capacity(), 0);
+                        target.push((node.level, mem::take(list)));
+                        node.count_key_len();
+                    }
+
+                    Vec::new()
+                }
+                TrieNodeKind::Blocks(_) => unreachable!(),
+                TrieNodeKind::Burst(table) => (0..table.nodes().len()).map(|x| (x, ())).collect(),
+            }
+        }, |node, _| {
+            if let TrieNodeKind::Burst(_) = &node.inner {
+                node.len = node.matches.len() + node.inner_len();
+                node.count_key_len();
+            }
+        });
+    }
+
+    /// Number of items in this subtree, excluding its matches.
+    fn inner_len(&self) -> usize {
+        match &self.inner {
+            TrieNodeKind::List(list) => list.len(),
+            TrieNodeKind::Blocks(blocks) => blocks.len(),
+            TrieNodeKind::Burst(table) => table.nodes().iter().map(|x| x.len).sum(),
+        }
+    }
+
+    /// Removes every item from the trie.
+    fn clear(&mut self, memory: &mut Memory) {
+        let mut pending = vec![self];
+
+        while let Some(node) = pending.pop() {
+            node.matches.clear();
+            node.len = 0;
+            node.key_len = 0;
+
+            match &mut node.inner {
+                TrieNodeKind::List(list) => list.clear(),
+                TrieNodeKind::Blocks(blocks) => {
+                    memory.resize::<T>(blocks.capacity(), 0);
+                    *blocks = Blocks::default();
+                }
+                TrieNodeKind::Burst(table) => pending.extend(table.nodes_mut()),
+            }
+        }
+    }
+
+    /// Finds the item of an ordered trie with the longest key which is a prefix of the given key.
+    fn longest_prefix_match(&self, key: &[I]) -> Option<&T> {
+        let mut node = self;
+        let mut best = None;
+
+        loop {
+            // matches along the path are exactly the prefixes of the key
+            if let Some(x) = node.matches.first() {
+                best = Some(x);
+            }
+
+            if key.len() <= node.level {
+                return best;
+            }
+
+            match &node.inner {
+                TrieNodeKind::List(list) => {
+                    // try the remaining prefixes, longest first
+                    for len in (node.level + 1..=key.len()).rev() {
+                        if let Some(index) = search(list, &node.keys, node.level, &key[..len]) {
+                            return Some(&list[index]);
+                        }
+                    }
+
+                    return best;
+                }
+                TrieNodeKind::Blocks(_) => unreachable!(),
+                TrieNodeKind::Burst(table) => {
+                    // keys below the node are longer than the key unless it follows the skipped
+                    // path
+                    match node.child_radix(key).and_then(|radix| table.get(radix)) {
+                        Some(child) => node = child,
+                        None => return best,
+                    }
+                }
+            }
+        }
+    }
+
+    /// Iterates over the items of an ordered trie with keys inside the bounds, in order.
+    ///
+    /// Whole subtrees outside of the bounds are skipped without being visited.
+    fn range(&self, lower: Bound<Vec<I>>, upper: Bound<Vec<I>>) -> Range<'_, C, T, I, K> {
+        let bounds = KeyBounds::new(lower, upper);
+        let (lower_tight, upper_tight) = bounds.is_bounded();
+
+        Range {
+            keys: &self.keys,
+            bounds,
+            current: [].iter(),
+            pending: vec![RangePending::Node(self, lower_tight, upper_tight)],
+        }
+    }
+
+    /// Drains the node into the sink, sorting buckets first unless they are already `sorted`.
+    ///
+    /// `lcp` is the length of the common prefix between the next item drained and the last one,
+    /// and is updated as items are drained.
+    fn drain_into<S: MergeSink<T>>(&mut self, sink: &mut S, lcp: &mut usize, sorted: bool,
+                                   memory: &mut Memory) {
+        // nodes still to drain, each with the level of the table leading to it, which bounds the
+        // prefix its items share with the ones drained before
+        let mut pending = vec![(self, usize::MAX)];
+
+        while let Some((node, bound)) = pending.pop() {
+            *lcp = (*lcp).min(bound);
+
+            node.flatten_blocks(memory);
+            node.len = 0;
+            node.key_len = 0;
+
+            // append exact matches for node first
+            if !node.matches.is_empty() {
+                sink.matches(&mut node.matches, node.level, *lcp);
+                *lcp = node.level;
+            }
+
+            match &mut node.inner {
+                TrieNodeKind::List(list) => {
+                    if !list.is_empty() {
+                        // now sort internal collection and append
+                        if !sorted {
+                            let hint_long = node.config.borrow().hint_long;
+                            sort_bucket(list, &node.keys, node.level, hint_long);
+                        }
+
+                        sink.bucket(list, node.level, *lcp);
+                        *lcp = node.level;
+                    }
+                }
+                TrieNodeKind::Blocks(_) => unreachable!(),
+                TrieNodeKind::Burst(table) => {
+                    let level = node.level + node.skip.len();
+
+                    // sequentially merge each table entry
+                    pending.extend(table.nodes_mut().iter_mut().rev().map(|x| (x, level)));
+                }
+            }
+        }
+    }
+
+    /// Converts the trie into an iterator over its items in order.
+    ///
+    /// Buckets are only sorted once the iterator reaches them.
+    fn into_sorted_iter(self) -> IntoSortedIter<C, T, I, K> {
+        IntoSortedIter {
+            keys: self.keys.clone(),
+            long: self.config.borrow().hint_long,
+            current: Vec::new().into_iter(),
+            pending: vec![Pending::Node(self)],
+        }
+    }
+
+    /// Sorts and drains the first `k` items of the trie into the target, in order.
+    ///
+    /// Only the buckets holding those items are sorted, and the rest are dropped.
+    fn merge_top_k(&mut self, target: &mut Vec<T>, k: usize, memory: &mut Memory) {
+        let long = self.config.borrow().hint_long;
+        let mut segments = Vec::new();
+        let mut remaining = k;
+
+        self.top_k_segments(&mut segments, &mut remaining, memory);
+
+        for segment in &mut segments {
+            segment.prepare(long);
+        }
+
+        for segment in segments {
+            target.append(segment.items);
+        }
+
+        self.clear(memory);
+    }
+
+    /// Collects, in order, the groups of items needed to make up the next `remaining` items.
+    fn top_k_segments<'a>(&'a mut self, segments: &mut Vec<Segment<'a, T, K>>,
+                          remaining: &mut usize, memory: &mut Memory) {
+        let mut pending = vec![self];
+
+        while let Some(node) = pending.pop() {
+            if *remaining == 0 {
+                return;
+            }
+
+            node.flatten_blocks(memory);
+
+            if !node.matches.is_empty() {
+                let take = node.matches.len().min(*remaining);
+                *remaining -= take;
+
+                segments.push(Segment {
+                    items: &mut node.matches,
+                    keys: &node.keys,
+                    level: node.level,
+                    take,
+                    sort: false,
+                });
+            }
+
+            match &mut node.inner {
+                TrieNodeKind::List(list) => {
+                    if !list.is_empty() && *remaining > 0 {
+                        let take = list.len().min(*remaining);
+                        *remaining -= take;
+
+                        segments.push(Segment {
+                            items: list,
+                            keys: &node.keys,
+                            level: node.level,
+                            take,
+                            sort: true,
+                        });
+                    }
+                }
+                TrieNodeKind::Blocks(_) => unreachable!(),
+                TrieNodeKind::Burst(table) => pending.extend(table.nodes_mut().iter_mut().rev()),
+            }
+        }
+    }
+
+}
+
+#[cfg(feature = "parallelization")]
+impl<C, T, I, K> TrieNode<C, T, I, K>
+    where C: Borrow<BurstConfig> + Clone + Send + Sync,
+          T: Send + Sync,
+          I: Into<usize> + Clone + Ord + Send + Sync,
+          K: KeySource<T, I> + Clone + Send + Sync
+{
+    /// Parallel version of [Trie::merge_into].
+    fn par_merge_into<S: MergeSink<T>>(&mut self, sink: &mut S, memory: &mut Memory) {
+        rayon::scope(|s| {
+            self.par_sort(s, memory);
+        });
+
+        self.drain_into(sink, &mut 0, true, memory);
+    }
+
+    /// Parallel version of [TrieNode::merge_top_k].
+    fn par_merge_top_k(&mut self, target: &mut Vec<T>, k: usize, memory: &mut Memory) {
+        let long = self.config.borrow().hint_long;
+        let mut segments = Vec::new();
+        let mut remaining = k;
+
+        self.top_k_segments(&mut segments, &mut remaining, memory);
+
+        segments.par_iter_mut().for_each(|segment| segment.prepare(long));
+
+        for segment in segments {
+            target.append(segment.items);
+        }
+
+        self.clear(memory);
+    }
+
+    /// Spawns a sort of every bucket onto the scope. The trie is walked on the calling thread, so
+    /// that deep tries do not recurse on the small stacks of worker threads.
+    fn par_sort<'scope>(&'scope mut self, scope: &rayon::Scope<'scope>, memory: &mut Memory) {
+        let mut pending = vec![self];
+
+        while let Some(node) = pending.pop() {
+            node.flatten_blocks(memory);
+
+            let long = node.config.borrow().hint_long;
+            let level = node.level;
+            let keys = &node.keys;
+            match &mut node.inner {
+                TrieNodeKind::List(list) => {
+                    if !list.is_empty() {
+                        scope.spawn(move |_| {
+                            if long {
+                                list.par_sort_unstable_by(|lhs, rhs| {
+                                    let lhs_remaining = &keys.key(lhs)[level..];
+                                    let rhs_remaining = &keys.key(rhs)[level..];
+                                    lhs_remaining.cmp(rhs_remaining)
+                                });
+                            } else {
+                                list.par_sort_unstable_by(|lhs, rhs| {
+                                    keys.key(lhs).cmp(keys.key(rhs))
+                                });
+                            }
+                        })
+                    }
+                }
+                TrieNodeKind::Blocks(_) => unreachable!(),
+                TrieNodeKind::Burst(table) => pending.extend(table.nodes_mut()),
+            }
+        }
+    }
+}
+
+/// Sorts a bucket of items which all share their first `level` key elements.
+pub fn sort_bucket<T, I, K>(list: &mut [T], keys: &K, level: usize, long: bool)
+    where I: Ord,
+          K: KeySource<T, I>
+{
+    // if arrays may be long, best to only sort the remaining elements
+    if long {
+        list.sort_unstable_by(|lhs, rhs| {
+            let lhs_remaining = &keys.key(lhs)[level..];
+            let rhs_remaining = &keys.key(rhs)[level..];
+            lhs_remaining.cmp(rhs_remaining)
+        });
+    } else {
+        list.sort_unstable_by(|lhs, rhs| keys.key(lhs).cmp(keys.key(rhs)));
+    }
+}
+
+impl<C, T, I, K> Clone for TrieNode<C, T, I, K>
+    where C: Clone,
+          T: Clone,
+          I: Clone,
+          K: Clone
+{
+    fn clone(&self) -> Self {
+        let mut root = self.clone_node();
+
+        // copied one level at a time without recursing, as deep tries would overflow the stack
+        let mut pending = vec![(self, &mut root)];
+
+        while let Some((node, copy)) = pending.pop() {
+            if let (TrieNodeKind::Burst(table), TrieNodeKind::Burst(copy_table)) =
+                (&node.inner, &mut copy.inner) {
+                copy_table.nodes = table.nodes.iter().map(Self::clone_node).collect();
+                pending.extend(table.nodes.iter().zip(&mut copy_table.nodes));
+            }
+        }
+
+        root
+    }
+}
+
+impl<C, T, I, K> TrieNode<C, T, I, K>
+    where C: Clone,
+          T: Clone,
+          I: Clone,
+          K: Clone
+{
+    /// Copies the node, leaving the table of a burst node without its children.
+    fn clone_node(&self) -> Self {
+        let inner = match &self.inner {
+            TrieNodeKind::List(list) => TrieNodeKind::List(list.clone()),
+            TrieNodeKind::Blocks(blocks) => TrieNodeKind::Blocks(blocks.clone()),
+            TrieNodeKind::Burst(table) => TrieNodeKind::Burst(Table {
+                nodes: Vec::new(),
+                radixes: table.radixes.clone(),
+            }),
+        };
+
+        Self {
+            level: self.level,
+            config: self.config.clone(),
+            keys: self.keys.clone(),
+            len: self.len,
+            key_len: self.key_len,
+            matches: self.matches.clone(),
+            skip: self.skip.clone(),
+            inner,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<C, T, I, K> TrieNode<C, T, I, K> {
+    /// Bytes allocated by the subtree, as they would be counted by [Memory].
+    fn heap_size(&self) -> usize {
+        let mut size = 0;
+        let mut pending = vec![self];
+
+        while let Some(node) = pending.pop() {
+            size += node.matches.capacity() * mem::size_of::<T>();
+
+            match &node.inner {
+                TrieNodeKind::List(list) => size += list.capacity() * mem::size_of::<T>(),
+                TrieNodeKind::Blocks(blocks) => size += blocks.capacity() * mem::size_of::<T>(),
+                TrieNodeKind::Burst(table) => {
+                    size += table.heap_size();
+                    pending.extend(table.nodes());
+                }
+            }
+        }
+
+        size
+    }
+
+    /// Frees the storage of this node, returning the children of a burst node.
+    fn release(&mut self) -> Vec<Self> {
+        self.matches = Vec::new();
+
+        match mem::replace(&mut self.inner, TrieNodeKind::List(Vec::new())) {
+            TrieNodeKind::Burst(table) => table.into_nodes(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl<C, T, I, K> Drop for TrieNode<C, T, I, K> {
+    /// Frees the subtree one node at a time, as dropping deep tries field by field would recurse
+    /// once per level.
+    fn drop(&mut self) {
+        let mut pending = self.release();
+
+        while let Some(mut node) = pending.pop() {
+            pending.extend(node.release());
+        }
+    }
+}
+
+/// Child nodes of a burst node, in radix order.
+///
+/// Dense tables hold a node for every radix class, so that children are found by indexing.
+/// Sparse tables, used once a trie reaches its memory limit, only hold the nodes of the radixes
+/// seen so far.
+pub struct Table<N> {
+    nodes: Vec<N>,
+    /// Radix of each node, in increasing order, for sparse tables.
+    radixes: Option<Vec<usize>>,
+}
+
+impl<N> Table<N> {
+    fn dense(nodes: Vec<N>) -> Self {
+        Self {
+            nodes,
+            radixes: None,
+        }
+    }
+
+    fn sparse() -> Self {
+        Self {
+            nodes: Vec::new(),
+            radixes: Some(Vec::new()),
+        }
+    }
+
+    fn nodes(&self) -> &[N] {
+        &self.nodes
+    }
+
+    fn nodes_mut(&mut self) -> &mut [N] {
+        &mut self.nodes
+    }
+
+    fn into_nodes(self) -> Vec<N> {
+        self.nodes
+    }
+
+    /// Finds the index of the node for the radix.
+    fn find(&self, radix: usize) -> Option<usize> {
+        match &self.radixes {
+            Some(radixes) => radixes.binary_search(&radix).ok(),
+            None => Some(radix).filter(|&x| x < self.nodes.len()),
+        }
+    }
+
+    /// Finds the index of the first node with a radix of at least `radix`.
+    fn lower_bound(&self, radix: usize) -> usize {
+        match &self.radixes {
+            Some(radixes) => radixes.partition_point(|&x| x < radix),
+            None => radix.min(self.nodes.len()),
+        }
+    }
+
+    fn get(&self, radix: usize) -> Option<&N> {
+        self.find(radix).map(|index| &self.nodes[index])
+    }
+
+    fn get_mut(&mut self, radix: usize) -> Option<&mut N> {
+        self.find(radix).map(move |index| &mut self.nodes[index])
+    }
+
+    /// Finds the node for the radix, creating it with `make` if the table is sparse and has no
+    /// such node yet.
+    fn slot<F: FnOnce() -> N>(&mut self, radix: usize, memory: &mut Memory, make: F) -> &mut N {
+        let index = match &mut self.radixes {
+            Some(radixes) => match radixes.binary_search(&radix) {
+                Ok(index) => index,
+                Err(index) => {
+                    let before = self.nodes.capacity() * mem::size_of::<N>() +
+                        radixes.capacity() * mem::size_of::<usize>();
+
+                    radixes.insert(index, radix);
+                    self.nodes.insert(index, make());
+
+                    memory.resize::<u8>(before, self.heap_size());
+                    index
+                }
+            },
+            None => radix,
+        };
+
+        &mut self.nodes[index]
+    }
+
+    /// Radixes of the nodes, in order.
+    fn radixes(&self) -> impl Iterator<Item=usize> + '_ {
+        (0..self.nodes.len()).map(move |index| {
+            self.radixes.as_ref().map_or(index, |radixes| radixes[index])
+        })
+    }
+
+    /// Bytes allocated by the table itself, not counting the storage of its nodes.
+    fn heap_size(&self) -> usize {
+        let radixes = self.radixes.as_ref().map_or(0, Vec::capacity);
+
+        self.nodes.capacity() * mem::size_of::<N>() + radixes * mem::size_of::<usize>()
+    }
+}
+
+/// Approximate count of the bytes allocated by a trie, kept by [Trie] and passed down to the
+/// nodes which allocate or free storage.
+///
+/// Items are counted by their size, not including anything they point to. The count is only
+/// kept for tries with a memory limit, and is left empty otherwise.
+struct Memory(Option<usize>);
+
+impl Memory {
+    /// Starts counting from the storage of a trie, if its config sets a memory limit.
+    fn count<C, T, I, K>(root: &TrieNode<C, T, I, K>) -> Self
+        where C: Borrow<BurstConfig>
+    {
+        Self(root.config.borrow().max_memory.map(|_| root.heap_size()))
+    }
+
+    fn is_counted(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Whether the count has reached the limit.
+    fn reached(&self, max: usize) -> bool {
+        self.0.is_some_and(|used| used >= max)
+    }
+
+    fn add(&mut self, bytes: usize) {
+        if let Some(used) = &mut self.0 {
+            *used += bytes;
+        }
+    }
+
+    /// Records a buffer of `E` changing capacity.
+    fn resize<E>(&mut self, before: usize, after: usize) {
+        if let Some(used) = &mut self.0 {
+            let size = mem::size_of::<E>();
+
+            if after >= before {
+                *used += (after - before) * size;
+            } else {
+                let freed = (before - after) * size;
+                debug_assert!(freed <= *used);
+                *used -= freed;
+            }
+        }
+    }
+}
+
+/// Stably merges two sorted buckets of items which share their first `level` key elements.
+fn merge_sorted<T, I, K>(left: Vec<T>, right: Vec<T>, keys: &K, level: usize) -> Vec<T>
+    where I: Ord,
+          K: KeySource<T, I>
+{
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+
+    while let (Some(x), Some(y)) = (left.peek(), right.peek()) {
+        // ties go to the left, so that equal items keep their order
+        if keys.key(y)[level..] < keys.key(x)[level..] {
+            merged.extend(right.next());
+        } else {
+            merged.extend(left.next());
+        }
+    }
+
+    merged.extend(left);
+    merged.extend(right);
+
+    merged
+}
+
+/// Merges two sorted buckets of items with distinct keys which share their first `level` key
+/// elements, combining the items of keys found in both.
+fn merge_sorted_with<T, I, K, F>(left: Vec<T>, right: Vec<T>, keys: &K, level: usize,
+                                 combine: &mut F) -> Vec<T>
+    where I: Ord,
+          K: KeySource<T, I>,
+          F: FnMut(&mut T, T)
+{
+    let mut merged: Vec<T> = Vec::with_capacity(left.len() + right.len());
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+
+    while let (Some(x), Some(y)) = (left.peek(), right.peek()) {
+        match keys.key(x)[level..].cmp(&keys.key(y)[level..]) {
+            Ordering::Less => merged.extend(left.next()),
+            Ordering::Greater => merged.extend(right.next()),
+            Ordering::Equal => {
+                let mut x = left.next().unwrap();
+                combine(&mut x, right.next().unwrap());
+                merged.push(x);
+            }
+        }
+    }
+
+    merged.extend(left);
+    merged.extend(right);
+
+    merged
+}
+
+/// Set operation to combine the distinct keys of two tries with.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SetOp {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl SetOp {
+    /// Whether a key present in the left and/or right trie is part of the result.
+    fn keep(self, in_left: bool, in_right: bool) -> bool {
+        match self {
+            SetOp::Union => in_left || in_right,
+            SetOp::Intersection => in_left && in_right,
+            SetOp::Difference => in_left && !in_right,
+        }
+    }
+}
+
+/// Combines two sorted buckets of items which share their first `level` key elements, appending
+/// one item per distinct key selected by the set operation to the target.
+///
+/// Items from the left bucket are preferred when both have the key.
+fn merge_sets<T, I, K>(left: Vec<T>, right: Vec<T>, keys: &K, level: usize, op: SetOp,
+                       target: &mut Vec<T>)
+    where I: Ord,
+          K: KeySource<T, I>
+{
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+
+    loop {
+        let order = match (left.peek(), right.peek()) {
+            (Some(x), Some(y)) => keys.key(x)[level..].cmp(&keys.key(y)[level..]),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => break,
+        };
+
+        let (x, y) = match order {
+            Ordering::Less => (next_distinct(&mut left, keys, level), None),
+            Ordering::Greater => (None, next_distinct(&mut right, keys, level)),
+            Ordering::Equal => {
+                (next_distinct(&mut left, keys, level), next_distinct(&mut right, keys, level))
+            }
+        };
+
+        if op.keep(x.is_some(), y.is_some()) {
+            target.extend(x.or(y));
+        }
+    }
+}
+
+/// Takes the next item of a sorted bucket, skipping any items with equal keys after it.
+fn next_distinct<T, I, K>(items: &mut Peekable<vec::IntoIter<T>>, keys: &K, level: usize)
+                          -> Option<T>
+    where I: Ord,
+          K: KeySource<T, I>
+{
+    let first = items.next()?;
+
+    while items.peek().filter(|x| keys.key(x)[level..] == keys.key(&first)[level..]).is_some() {
+        items.next();
+    }
+
+    Some(first)
+}
+
+/// Collapse burst nodes in ordered tries once they hold fewer items than the burst limit divided
+/// by this.
+const UNBURST_DIVISOR: usize = 4;
+
+/// Copies the key of a bound, so that iterators do not hold on to the caller's range.
+pub fn to_owned_bound<Q, I>(bound: Bound<&Q>) -> Bound<Vec<I>>
+    where Q: AsRef<[I]> + ?Sized,
+          I: Clone
+{
+    match bound {
+        Bound::Included(x) => Bound::Included(x.as_ref().to_vec()),
+        Bound::Excluded(x) => Bound::Excluded(x.as_ref().to_vec()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+/// Finds the first item in a sorted bucket with the given key.
+fn search<T, I, K>(list: &[T], keys: &K, level: usize, key: &[I]) -> Option<usize>
+    where I: Ord,
+          K: KeySource<T, I>
+{
+    let key = &key[level..];
+    let index = list.partition_point(|x| &keys.key(x)[level..] < key);
+
+    list.get(index)
+        .filter(|x| &keys.key(x)[level..] == key)
+        .map(|_| index)
+}
+
+enum PrefixMatch<'a, C, T, I, K> {
+    /// Every item below this node has the prefix.
+    Subtree(&'a TrieNode<C, T, I, K>),
+    /// Every item below the children in this table has the prefix.
+    Children(&'a Table<TrieNode<C, T, I, K>>),
+    /// Only these items have the prefix.
+    Bucket(&'a [T]),
+}
+
+/// Group of items from a single node, of which only the first `take` are wanted.
+struct Segment<'a, T, K> {
+    items: &'a mut Vec<T>,
+    keys: &'a K,
+    level: usize,
+    take: usize,
+    sort: bool,
+}
+
+impl<'a, T, K> Segment<'a, T, K> {
+    /// Discards unwanted items, and sorts the rest if needed.
+    fn prepare<I>(&mut self, long: bool)
+        where I: Ord,
+              K: KeySource<T, I>
+    {
+        let (keys, level) = (self.keys, self.level);
+
+        if !self.sort {
+            self.items.truncate(self.take);
+            return;
+        }
+
+        if self.take < self.items.len() {
+            // partition so that the wanted items come first
+            if long {
+                self.items.select_nth_unstable_by(self.take, |lhs, rhs| {
+                    keys.key(lhs)[level..].cmp(&keys.key(rhs)[level..])
+                });
+            } else {
+                self.items.select_nth_unstable_by(self.take, |lhs, rhs| {
+                    keys.key(lhs).cmp(keys.key(rhs))
+                });
+            }
+
+            self.items.truncate(self.take);
+        }
+
+        sort_bucket(self.items, keys, level, long);
+    }
+}
+
+/// Iterator over the items of a trie in order, created by [TrieNode::into_sorted_iter].
+pub struct IntoSortedIter<C, T, I, K = ItemKeys> {
+    keys: K,
+    long: bool,
+    current: std::vec::IntoIter<T>,
+    /// Nodes and buckets still to be visited, with the next one last.
+    pending: Vec<Pending<C, T, I, K>>,
+}
+
+enum Pending<C, T, I, K> {
+    Node(TrieNode<C, T, I, K>),
+    Bucket(Vec<T>, usize),
+}
+
+impl<C, T, I, K> Iterator for IntoSortedIter<C, T, I, K>
+    where I: Ord,
+          K: KeySource<T, I>
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        loop {
+            if let Some(x) = self.current.next() {
+                return Some(x);
+            }
+
+            match self.pending.pop()? {
+                Pending::Node(mut node) => {
+                    match mem::replace(&mut node.inner, TrieNodeKind::List(Vec::new())) {
+                        TrieNodeKind::List(list) => {
+                            if !list.is_empty() {
+                                self.pending.push(Pending::Bucket(list, node.level));
+                            }
+                        }
+                        TrieNodeKind::Blocks(blocks) => {
+                            if !blocks.is_empty() {
+                                self.pending.push(Pending::Bucket(blocks.into_vec(), node.level));
+                            }
+                        }
+                        TrieNodeKind::Burst(table) => {
+                            let nodes = table.into_nodes().into_iter().rev();
+                            self.pending.extend(nodes.map(Pending::Node));
+                        }
+                    }
+
+                    // exact matches come before everything else in the node
+                    self.current = mem::take(&mut node.matches).into_iter();
+                }
+                Pending::Bucket(mut list, level) => {
+                    sort_bucket(&mut list, &self.keys, level, self.long);
+
+                    self.current = list.into_iter();
+                }
+            }
+        }
+    }
+}
+
+/// Iterator over the items of an ordered trie, created by [TrieNode::iter].
+pub struct Iter<'a, C, T, I, K = ItemKeys> {
+    current: std::slice::Iter<'a, T>,
+    /// Nodes and buckets still to be visited, with the next one last.
+    pending: Vec<IterPending<'a, C, T, I, K>>,
+}
+
+enum IterPending<'a, C, T, I, K> {
+    Node(&'a TrieNode<C, T, I, K>),
+    Bucket(&'a [T]),
+}
+
+impl<'a, C, T, I, K> Clone for Iter<'a, C, T, I, K> {
+    fn clone(&self) -> Self {
+        Self {
+            current: self.current.clone(),
+            pending: self.pending.clone(),
+        }
+    }
+}
+
+impl<'a, C, T, I, K> Clone for IterPending<'a, C, T, I, K> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, C, T, I, K> Copy for IterPending<'a, C, T, I, K> {}
+
+impl<'a, C, T, I, K> Iterator for Iter<'a, C, T, I, K> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            if let Some(x) = self.current.next() {
+                return Some(x);
+            }
+
+            match self.pending.pop()? {
+                IterPending::Node(node) => {
+                    match &node.inner {
+                        TrieNodeKind::List(list) => {
+                            if !list.is_empty() {
+                                self.pending.push(IterPending::Bucket(list));
+                            }
+                        }
+                        TrieNodeKind::Blocks(_) => unreachable!(),
+                        TrieNodeKind::Burst(table) => {
+                            self.pending.extend(table.nodes().iter().rev().map(IterPending::Node));
+                        }
+                    }
+
+                    // exact matches come before everything else in the node
+                    self.current = node.matches.iter();
+                }
+                IterPending::Bucket(list) => {
+                    self.current = list.iter();
+                }
+            }
+        }
+    }
+}
+
+/// Iterator over the items of an ordered trie within a range of keys, created by
+/// [TrieNode::range].
+pub struct Range<'a, C, T, I, K = ItemKeys> {
+    keys: &'a K,
+    bounds: KeyBounds<I>,
+    current: std::slice::Iter<'a, T>,
+    /// Nodes and buckets still to be visited, with the next one last.
+    pending: Vec<RangePending<'a, C, T, I, K>>,
+}
+
+enum RangePending<'a, C, T, I, K> {
+    /// Node to visit, along with whether the path leading to it matches the lower and upper
+    /// bounds so far. Only then do its items still have to be checked against those bounds.
+    Node(&'a TrieNode<C, T, I, K>, bool, bool),
+    /// Bucket which has already been restricted to the bounds.
+    Bucket(&'a [T]),
+}
+
+impl<'a, C, T, I, K> Iterator for Range<'a, C, T, I, K>
+    where I: Into<usize> + Clone + Ord,
+          K: KeySource<T, I>
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            if let Some(x) = self.current.next() {
+                return Some(x);
+            }
+
+            match self.pending.pop()? {
+                RangePending::Node(node, lower_tight, upper_tight) => {
+                    match &node.inner {
+                        TrieNodeKind::List(list) => {
+                            let range = self.bounds.clamp(list, self.keys, lower_tight,
+                                                          upper_tight);
+
+                            if !range.is_empty() {
+                                self.pending.push(RangePending::Bucket(&list[range]));
+                            }
+                        }
+                        TrieNodeKind::Blocks(_) => unreachable!(),
+                        TrieNodeKind::Burst(table) => {
+                            let children = self.bounds.children(node.level, &node.skip, table,
+                                                                lower_tight, upper_tight);
+
+                            for (index, lower_tight, upper_tight) in children.rev() {
+                                let child = &table.nodes()[index];
+                                self.pending.push(RangePending::Node(child, lower_tight,
+                                                                     upper_tight));
+                            }
+                        }
+                    }
+
+                    // exact matches come before everything else in the node
+                    let range = self.bounds.clamp(&node.matches, self.keys, lower_tight,
+                                                  upper_tight);
+                    self.current = node.matches[range].iter();
+                }
+                RangePending::Bucket(list) => {
+                    self.current = list.iter();
+                }
+            }
+        }
+    }
+}
+
+/// Lower and upper bounds on keys, for range queries.
+///
+/// While walking down the trie, a node is "tight" against a bound when the path leading to it
+/// matches that bound so far. Items below nodes which are not tight are always within the bound.
+pub struct KeyBounds<I> {
+    lower: Bound<Vec<I>>,
+    upper: Bound<Vec<I>>,
+}
+
+impl<I> KeyBounds<I>
+    where I: Into<usize> + Clone + Ord
+{
+    pub fn new(lower: Bound<Vec<I>>, upper: Bound<Vec<I>>) -> Self {
+        Self { lower, upper }
+    }
+
+    /// Whether the root is tight against the lower and upper bounds.
+    fn is_bounded(&self) -> (bool, bool) {
+        (!matches!(self.lower, Bound::Unbounded), !matches!(self.upper, Bound::Unbounded))
+    }
+
+    fn above_lower(&self, key: &[I]) -> bool {
+        match &self.lower {
+            Bound::Included(lower) => key >= &lower[..],
+            Bound::Excluded(lower) => key > &lower[..],
+            Bound::Unbounded => true,
+        }
+    }
+
+    fn below_upper(&self, key: &[I]) -> bool {
+        match &self.upper {
+            Bound::Included(upper) => key <= &upper[..],
+            Bound::Excluded(upper) => key < &upper[..],
+            Bound::Unbounded => true,
+        }
+    }
+
+    /// Finds the part of a sorted group of items which is within the bounds.
+    fn clamp<T, K>(&self, items: &[T], keys: &K, lower_tight: bool, upper_tight: bool)
+                   -> std::ops::Range<usize>
+        where K: KeySource<T, I>
+    {
+        let start = match lower_tight {
+            true => items.partition_point(|x| !self.above_lower(keys.key(x))),
+            false => 0,
+        };
+
+        let end = match upper_tight {
+            true => items.partition_point(|x| self.below_upper(keys.key(x))),
+            false => items.len(),
+        };
+
+        start..end.max(start)
+    }
+
+    /// Finds the indices of the children of a node which may hold items within the bounds, along
+    /// with whether each child is tight against the lower and upper bounds.
+    fn children<N>(&self, level: usize, skip: &[I], table: &Table<N>, lower_tight: bool,
+                   upper_tight: bool) -> impl DoubleEndedIterator<Item=(usize, bool, bool)> {
+        // every key below the node follows its skipped path, which may settle either bound
+        let (lower_tight, upper_tight, outside) =
+            match self.skip(level, skip, lower_tight, upper_tight) {
+                Some((lower_tight, upper_tight)) => (lower_tight, upper_tight, false),
+                None => (false, false, true),
+            };
+        let level = level + skip.len();
+
+        // radix of each bound at this level, if the path so far matches that bound
+        let lower_radix = match (&self.lower, lower_tight) {
+            (Bound::Included(x), true) | (Bound::Excluded(x), true) => {
+                x.get(level).map(|radix| radix.clone().into())
+            }
+            _ => None,
+        };
+
+        let (upper_radix, upper_ended) = match (&self.upper, upper_tight) {
+            (Bound::Included(x), true) | (Bound::Excluded(x), true) => match x.get(level) {
+                Some(radix) => (Some(radix.clone().into()), false),
+                // every key below this node extends the upper bound, so is above it
+                None => (None, true),
+            },
+            _ => (None, false),
+        };
+
+        let start = table.lower_bound(lower_radix.unwrap_or(0));
+        let end = match (outside || upper_ended, upper_radix) {
+            (true, _) => start,
+            (false, Some(radix)) => table.lower_bound(radix + 1).max(start),
+            (false, None) => table.nodes().len(),
+        };
+
+        let lower_index = lower_radix.and_then(|radix| table.find(radix));
+        let upper_index = upper_radix.and_then(|radix| table.find(radix));
+
+        (start..end).map(move |index| {
+            (index, lower_index == Some(index), upper_index == Some(index))
+        })
+    }
+
+    /// Follows the skipped path of a node at `level` along the bounds it is tight against.
+    /// Returns whether the path still matches each bound, or `None` if the keys following it are
+    /// all outside of them.
+    fn skip(&self, level: usize, skip: &[I], lower_tight: bool, upper_tight: bool)
+            -> Option<(bool, bool)> {
+        let shared = |rest: &[I]| skip.iter().zip(rest).take_while(|(x, y)| x == y).count();
+
+        let lower_tight = match Self::rest(&self.lower, lower_tight, level) {
+            Some(rest) => match shared(rest) {
+                shared if shared == skip.len() => true,
+                // the keys extend the bound, so are above it
+                shared if shared == rest.len() => false,
+                shared if skip[shared] < rest[shared] => return None,
+                _ => false,
+            },
+            None => false,
+        };
+
+        let upper_tight = match Self::rest(&self.upper, upper_tight, level) {
+            Some(rest) => match shared(rest) {
+                shared if shared == skip.len() => true,
+                shared if shared == rest.len() => return None,
+                shared if skip[shared] < rest[shared] => false,
+                _ => return None,
+            },
+            None => false,
+        };
+
+        Some((lower_tight, upper_tight))
+    }
+
+    /// Part of a bound past `level`, if the path so far matches it.
+    fn rest(bound: &Bound<Vec<I>>, tight: bool, level: usize) -> Option<&[I]> {
+        match (bound, tight) {
+            (Bound::Included(x), true) | (Bound::Excluded(x), true) => {
+                Some(x.get(level..).unwrap_or(&[]))
+            }
+            _ => None,
+        }
+    }
+}